@@ -0,0 +1,171 @@
+//! Cycle detection for sequences that repeatedly apply a step function to some state. As long as
+//! there are finitely many states, every such sequence eventually repeats.
+
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+};
+
+/// Where a sequence starts repeating.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Cycle {
+    /// The number of steps before the sequence enters the cycle.
+    pub prefix: usize,
+    /// The number of steps it takes to go around the cycle once.
+    pub period: usize,
+}
+
+impl Cycle {
+    /// The earliest step at which the sequence is in the same state as at step `n`.
+    pub fn index(&self, n: usize) -> usize {
+        if n < self.prefix {
+            n
+        } else {
+            self.prefix + (n - self.prefix) % self.period
+        }
+    }
+
+    /// The state at step `n`, taking at most `prefix + period` steps.
+    pub fn nth<S>(&self, start: S, mut step: impl FnMut(&S) -> S, n: usize) -> S {
+        (0..self.index(n)).fold(start, |state, _| step(&state))
+    }
+}
+
+/// Brent's algorithm, which only keeps two states around at any time.
+pub fn brent<S: Clone + Eq>(start: &S, mut step: impl FnMut(&S) -> S) -> Cycle {
+    // Find the period by moving the tortoise to the hare at every power of two.
+    let mut power = 1;
+    let mut period = 1;
+    let mut tortoise = start.clone();
+    let mut hare = step(start);
+    while tortoise != hare {
+        if power == period {
+            tortoise = hare.clone();
+            power *= 2;
+            period = 0;
+        }
+        hare = step(&hare);
+        period += 1;
+    }
+
+    // With the hare one period ahead, they meet where the cycle starts.
+    let mut tortoise = start.clone();
+    let mut hare = (0..period).fold(start.clone(), |state, _| step(&state));
+    let mut prefix = 0;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        prefix += 1;
+    }
+
+    Cycle { prefix, period }
+}
+
+/// Remembers a hash of every state instead of the state itself. When a hash comes up again, the
+/// earlier state is recomputed from the start to rule out collisions.
+pub fn fingerprint<S: Clone + Hash + Eq>(start: &S, mut step: impl FnMut(&S) -> S) -> Cycle {
+    let mut seen: HashMap<u64, Vec<usize>> = HashMap::new();
+    let mut current = start.clone();
+
+    for steps in 0.. {
+        let hash = hash(&current);
+        if let Some(candidates) = seen.get(&hash) {
+            for &candidate in candidates {
+                let earlier = (0..candidate).fold(start.clone(), |state, _| step(&state));
+                if earlier == current {
+                    return Cycle {
+                        prefix: candidate,
+                        period: steps - candidate,
+                    };
+                }
+            }
+        }
+        seen.entry(hash).or_default().push(steps);
+        current = step(&current);
+    }
+
+    unreachable!()
+}
+
+fn hash<S: Hash>(state: &S) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    state.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::hash::{Hash, Hasher};
+
+    use crate::{brent, fingerprint, Cycle};
+
+    #[test]
+    fn test_rho() {
+        // Nothing squares to -1 modulo 61, so 0 is never reached again and comes before the cycle.
+        let step = |x: &u64| (x * x + 1) % 61;
+        let expected = naive(&0, step);
+        assert!(expected.prefix > 0);
+
+        assert_eq!(brent(&0, step), expected);
+        assert_eq!(fingerprint(&0, step), expected);
+        for n in 0..200 {
+            let stepped = (0..n).fold(0, |x, _| step(&x));
+            assert_eq!(expected.nth(0, step, n), stepped);
+        }
+    }
+
+    #[test]
+    fn test_pure_cycle() {
+        let step = |x: &u32| (x + 1) % 7;
+
+        assert_eq!(
+            brent(&3, step),
+            Cycle {
+                prefix: 0,
+                period: 7
+            }
+        );
+        assert_eq!(
+            fingerprint(&3, step),
+            Cycle {
+                prefix: 0,
+                period: 7
+            }
+        );
+        assert_eq!(
+            brent(&0, |x: &u32| *x),
+            Cycle {
+                prefix: 0,
+                period: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_collisions() {
+        // Every state hashes the same, so only the comparison tells them apart.
+        #[derive(Clone, PartialEq, Eq)]
+        struct Colliding(u64);
+
+        impl Hash for Colliding {
+            fn hash<H: Hasher>(&self, _: &mut H) {}
+        }
+
+        let step = |x: &Colliding| Colliding((x.0 * 3 + 1) % 50);
+        assert_eq!(fingerprint(&Colliding(0), step), brent(&Colliding(0), step));
+    }
+
+    fn naive(start: &u64, step: impl Fn(&u64) -> u64) -> Cycle {
+        let mut seen = vec![*start];
+        loop {
+            let next = step(seen.last().unwrap());
+            if let Some(prefix) = seen.iter().position(|&x| x == next) {
+                return Cycle {
+                    prefix,
+                    period: seen.len() - prefix,
+                };
+            }
+            seen.push(next);
+        }
+    }
+}
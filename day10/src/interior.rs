@@ -0,0 +1,176 @@
+use std::collections::{BTreeSet, VecDeque};
+
+use crate::{Direction, Maze, Pipe, Pos};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Method {
+    /// Walks every row, toggling between inside and outside whenever the loop is crossed.
+    Scanline,
+    /// Treats the loop as a polygon, using its orientation to find the tiles on its inner side.
+    Polygon,
+    /// Fills the outside at three times the resolution, so it can squeeze between pipes.
+    FloodFill,
+}
+
+impl Maze {
+    /// All tiles enclosed by the loop, which has to be found first.
+    pub fn interior(&self, method: Method) -> BTreeSet<Pos> {
+        match method {
+            Method::Scanline => self.interior_scanline(),
+            Method::Polygon => self.interior_polygon(),
+            Method::FloodFill => self.interior_flood_fill(),
+        }
+    }
+
+    /// The number of enclosed tiles according to Pick's theorem, `A = i + b / 2 - 1`, with the
+    /// area `A` of the loop given by the shoelace formula.
+    pub fn pick_count(&self) -> usize {
+        let area = self.signed_double_area().unsigned_abs();
+        (area - self.path.len()) / 2 + 1
+    }
+
+    /// Twice the area enclosed by the loop, positive if it runs clockwise.
+    fn signed_double_area(&self) -> isize {
+        self.steps()
+            .map(|(from, to, _)| from.col * to.row - to.col * from.row)
+            .sum()
+    }
+
+    /// Every step along the loop, with the positions it goes from and to.
+    fn steps(&self) -> impl Iterator<Item = (Pos, Pos, Direction)> + '_ {
+        self.path
+            .iter()
+            .zip(self.path.iter().cycle().skip(1))
+            .map(|(&from, &to)| {
+                let direction = Direction::ALL
+                    .into_iter()
+                    .find(|&dir| from.move_in(dir) == to)
+                    .unwrap();
+                (from, to, direction)
+            })
+    }
+
+    fn interior_scanline(&self) -> BTreeSet<Pos> {
+        let on_loop: BTreeSet<_> = self.path.iter().copied().collect();
+        let mut interior = BTreeSet::new();
+
+        for (row, pipes) in self.pipes.iter().enumerate() {
+            let mut in_ring = false;
+            let mut previous: Option<Pipe> = None;
+
+            for (col, &pipe) in pipes.iter().enumerate() {
+                let pos = Pos {
+                    row: row as isize,
+                    col: col as isize,
+                };
+                if !on_loop.contains(&pos) {
+                    if in_ring {
+                        interior.insert(pos);
+                    }
+                    continue;
+                }
+
+                match pipe {
+                    Pipe::UpDown => in_ring = !in_ring,
+                    Pipe::LeftRight => (),
+                    Pipe::Start | Pipe::None => unreachable!(),
+                    pipe => match previous.take() {
+                        None => previous = Some(pipe),
+                        // A corner pair only crosses the loop if one goes up and the other down.
+                        Some(opener) => {
+                            if pipe.vertical() != opener.vertical() {
+                                in_ring = !in_ring;
+                            }
+                        }
+                    },
+                }
+            }
+        }
+
+        interior
+    }
+
+    fn interior_polygon(&self) -> BTreeSet<Pos> {
+        let on_loop: BTreeSet<_> = self.path.iter().copied().collect();
+        let clockwise = self.signed_double_area() > 0;
+
+        let seeds = self.steps().flat_map(|(from, to, direction)| {
+            let inside = if clockwise {
+                direction.clockwise()
+            } else {
+                direction.counter_clockwise()
+            };
+            [from.move_in(inside), to.move_in(inside)]
+        });
+
+        let mut interior = BTreeSet::new();
+        let mut queue: VecDeque<_> = seeds.collect();
+        while let Some(pos) = queue.pop_front() {
+            if self.get(pos).is_none() || on_loop.contains(&pos) || !interior.insert(pos) {
+                continue;
+            }
+            queue.extend(Direction::ALL.map(|dir| pos.move_in(dir)));
+        }
+
+        interior
+    }
+
+    fn interior_flood_fill(&self) -> BTreeSet<Pos> {
+        let height = self.pipes.len() * 3;
+        let width = self.pipes.iter().map(Vec::len).max().unwrap_or_default() * 3;
+
+        // Draw every pipe of the loop as a plus shape missing the arms it doesn't connect to.
+        let mut blocked = vec![vec![false; width]; height];
+        for &pos in &self.path {
+            let (row, col) = (pos.row as usize * 3 + 1, pos.col as usize * 3 + 1);
+            blocked[row][col] = true;
+            for direction in self.get(pos).unwrap().directions().unwrap() {
+                let arm = Pos {
+                    row: row as isize,
+                    col: col as isize,
+                }
+                .move_in(direction);
+                blocked[arm.row as usize][arm.col as usize] = true;
+            }
+        }
+
+        let mut outside = vec![vec![false; width]; height];
+        let mut queue: VecDeque<_> = (0..height)
+            .flat_map(|row| [(row, 0), (row, width - 1)])
+            .chain((0..width).flat_map(|col| [(0, col), (height - 1, col)]))
+            .collect();
+        while let Some((row, col)) = queue.pop_front() {
+            if blocked[row][col] || outside[row][col] {
+                continue;
+            }
+            outside[row][col] = true;
+            if row > 0 {
+                queue.push_back((row - 1, col));
+            }
+            if row + 1 < height {
+                queue.push_back((row + 1, col));
+            }
+            if col > 0 {
+                queue.push_back((row, col - 1));
+            }
+            if col + 1 < width {
+                queue.push_back((row, col + 1));
+            }
+        }
+
+        self.pipes
+            .iter()
+            .enumerate()
+            .flat_map(|(row, pipes)| {
+                (0..pipes.len()).map(move |col| Pos {
+                    row: row as isize,
+                    col: col as isize,
+                })
+            })
+            .filter(|pos| {
+                let (row, col) = (pos.row as usize * 3 + 1, pos.col as usize * 3 + 1);
+                !blocked[row][col] && !outside[row][col]
+            })
+            .collect()
+    }
+}
@@ -1,154 +1,136 @@
-use std::{
-    collections::{BTreeMap, BTreeSet},
-    str::FromStr,
-};
+mod interior;
+mod render;
 
-use itertools::Itertools;
+use std::{env, str::FromStr};
+
+use crate::{interior::Method, render::Style};
 
 fn main() {
     let input = include_str!("../../inputs/input10");
 
+    if env::args().nth(1).as_deref() == Some("render") {
+        let mut maze = Maze::from_str(input).unwrap();
+        maze.find_loop().unwrap();
+        print!("{}", maze.render(Style::detect()));
+        return;
+    }
+
     println!("Part One: {}", solve_10a(input));
-    println!("Part Two: {}", solve_10b(input));
+    match env::args().nth(1).as_deref() {
+        Some("polygon") => println!("Part Two: {}", count_interior(input, Method::Polygon)),
+        Some("flood-fill") => println!("Part Two: {}", count_interior(input, Method::FloodFill)),
+        Some("pick") => {
+            let mut maze = Maze::from_str(input).unwrap();
+            maze.find_loop().unwrap();
+            println!("Part Two: {}", maze.pick_count());
+        }
+        _ => println!("Part Two: {}", solve_10b(input)),
+    }
 }
 
 fn solve_10a(input: &str) -> u32 {
-    Maze::from_str(input).unwrap().solve()
+    Maze::from_str(input).unwrap().find_loop().unwrap()
 }
 
 fn solve_10b(input: &str) -> u32 {
-    let mut maze = Maze::from_str(input).unwrap();
-    maze.solve();
-    let mut path = maze.path.clone();
-    path.sort_by(|pos1, pos2| pos1.row.cmp(&pos2.row));
-    let xs: BTreeMap<isize, BTreeSet<isize>> = path
-        .into_iter()
-        .group_by(|pos| pos.row)
-        .into_iter()
-        .map(|(row, cols)| (row, cols.map(|pos| pos.col).collect()))
-        .collect();
-    let mut total = 0;
-
-    for row in 0..maze.height as isize {
-        let mut in_ring = false;
-        let xs = xs.get(&row).cloned().unwrap_or_default();
-        let clean_pipes: Vec<_> = maze.pipes[row as usize]
-            .iter()
-            .copied()
-            .enumerate()
-            .map(|(index, pipe)| match xs.get(&(index as isize)) {
-                None => Pipe::None,
-                Some(_) => pipe,
-            })
-            .collect();
-        let mut previous = None;
-        for pipe in clean_pipes {
-            match pipe {
-                Pipe::UpDown => in_ring = !in_ring,
-                Pipe::LeftRight => (),
-                Pipe::None => {
-                    if in_ring {
-                        total += 1;
-                    }
-                }
-                Pipe::Start => unreachable!(),
-                pipe => match previous {
-                    None => previous = Some(pipe),
-                    Some(opener) => {
-                        previous = None;
-                        if pipe
-                            .directions()
-                            .unwrap()
-                            .into_iter()
-                            .find(Direction::is_vertical)
-                            .unwrap()
-                            != opener
-                                .directions()
-                                .unwrap()
-                                .into_iter()
-                                .find(Direction::is_vertical)
-                                .unwrap()
-                        {
-                            in_ring = !in_ring;
-                        }
-                    }
-                },
-            }
-        }
-    }
+    count_interior(input, Method::Scanline)
+}
 
-    total
+fn count_interior(input: &str, method: Method) -> u32 {
+    let mut maze = Maze::from_str(input).unwrap();
+    maze.find_loop().unwrap();
+    maze.interior(method).len() as u32
 }
 
 struct Maze {
     pipes: Vec<Vec<Pipe>>,
-    height: usize,
     start: Pos,
     path: Vec<Pos>,
 }
 
 impl Maze {
-    fn solve(&mut self) -> u32 {
-        for assumption in Pipe::ALL {
-            let mut length = 0;
-            let mut current_pos = self.start;
-            self.path.push(current_pos);
-            let mut last_direction = assumption.directions().unwrap()[0];
-
-            current_pos = current_pos.move_in(last_direction);
-            self.path.push(current_pos);
-            length += 1;
-
-            loop {
-                match self.get(current_pos) {
-                    None | Some(Pipe::None) => {
-                        self.path.clear();
-                        break;
-                    }
-                    Some(Pipe::Start) => {
-                        *self.get_mut(self.start).unwrap() = assumption;
-                        return length / 2;
-                    }
-                    Some(current) => {
-                        let directions: Vec<_> = current
-                            .directions()
-                            .unwrap()
-                            .into_iter()
-                            .filter(|dir| *dir != last_direction.opposite())
-                            .collect();
-                        if directions.len() == 1 {
-                            last_direction = directions[0];
-                            current_pos = current_pos.move_in(last_direction);
-                            self.path.push(current_pos);
-                            length += 1;
-                        }
-                    }
-                }
+    /// Finds the loop through the start, and replaces the start with the pipe that closes it.
+    /// Returns the number of steps to the point of the loop farthest from the start.
+    fn find_loop(&mut self) -> Result<u32, MazeError> {
+        let connected: Vec<_> = Direction::ALL
+            .into_iter()
+            .filter(|&dir| {
+                self.get(self.start.move_in(dir))
+                    .and_then(|pipe| pipe.directions())
+                    .is_some_and(|dirs| dirs.contains(&dir.opposite()))
+            })
+            .collect();
+
+        let mut loops: Vec<_> = Pipe::ALL
+            .into_iter()
+            .filter(|pipe| {
+                pipe.directions()
+                    .unwrap()
+                    .iter()
+                    .all(|dir| connected.contains(dir))
+            })
+            .filter_map(|pipe| self.trace(pipe).map(|path| (pipe, path)))
+            .collect();
+
+        match loops.len() {
+            0 => Err(MazeError::NoLoop),
+            1 => {
+                let (pipe, path) = loops.pop().unwrap();
+                *self.get_mut(self.start).unwrap() = pipe;
+                self.path = path;
+                Ok(self.path.len() as u32 / 2)
+            }
+            _ => Err(MazeError::MultipleLoops),
+        }
+    }
+
+    /// Follows the pipes, assuming the start is `start_pipe`, and returns every position on the
+    /// way if that leads back to the start from the other side.
+    fn trace(&self, start_pipe: Pipe) -> Option<Vec<Pos>> {
+        let [mut direction, end] = start_pipe.directions().unwrap();
+        let mut path = vec![self.start];
+        let mut current = self.start.move_in(direction);
+
+        // Every pipe connects to at most two others, so the path cannot run into a loop that
+        // doesn't contain the start.
+        while current != self.start {
+            let from = direction.opposite();
+            let directions = self.get(current)?.directions()?;
+            if !directions.contains(&from) {
+                return None;
             }
+            direction = directions.into_iter().find(|dir| *dir != from).unwrap();
+            path.push(current);
+            current = current.move_in(direction);
         }
 
-        0
+        (direction.opposite() == end).then_some(path)
     }
 
     fn get(&self, pos: Pos) -> Option<Pipe> {
-        if pos.row < 0 || pos.col < 0 {
-            None
-        } else {
-            Some(self.pipes[pos.row as usize][pos.col as usize])
-        }
+        let row = usize::try_from(pos.row).ok()?;
+        let col = usize::try_from(pos.col).ok()?;
+        self.pipes.get(row)?.get(col).copied()
     }
 
     fn get_mut(&mut self, pos: Pos) -> Option<&mut Pipe> {
-        if pos.row < 0 || pos.col < 0 {
-            None
-        } else {
-            Some(&mut self.pipes[pos.row as usize][pos.col as usize])
-        }
+        let row = usize::try_from(pos.row).ok()?;
+        let col = usize::try_from(pos.col).ok()?;
+        self.pipes.get_mut(row)?.get_mut(col)
     }
 }
 
+#[derive(Debug, PartialEq, Eq)]
+enum MazeError {
+    MissingStart,
+    /// No pipe at the start leads back to it.
+    NoLoop,
+    /// More than one pipe at the start leads back to it.
+    MultipleLoops,
+}
+
 impl FromStr for Maze {
-    type Err = ();
+    type Err = MazeError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut pipes = Vec::new();
@@ -171,12 +153,9 @@ impl FromStr for Maze {
             pipes.push(current_row);
         }
 
-        let height = pipes.len();
-
         Ok(Maze {
             pipes,
-            height,
-            start: start.unwrap(),
+            start: start.ok_or(MazeError::MissingStart)?,
             path: Vec::new(),
         })
     }
@@ -228,6 +207,10 @@ impl Pipe {
             Pipe::Start | Pipe::None => None,
         }
     }
+
+    fn vertical(&self) -> Option<Direction> {
+        self.directions()?.into_iter().find(Direction::is_vertical)
+    }
 }
 
 #[derive(Copy, Clone, PartialEq)]
@@ -239,6 +222,13 @@ pub enum Direction {
 }
 
 impl Direction {
+    const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Right,
+        Direction::Down,
+        Direction::Left,
+    ];
+
     fn opposite(&self) -> Direction {
         match self {
             Direction::Up => Direction::Down,
@@ -248,6 +238,19 @@ impl Direction {
         }
     }
 
+    fn clockwise(&self) -> Direction {
+        match self {
+            Direction::Up => Direction::Right,
+            Direction::Right => Direction::Down,
+            Direction::Down => Direction::Left,
+            Direction::Left => Direction::Up,
+        }
+    }
+
+    fn counter_clockwise(&self) -> Direction {
+        self.clockwise().opposite()
+    }
+
     fn is_vertical(&self) -> bool {
         match self {
             Direction::Up | Direction::Down => true,
@@ -256,7 +259,7 @@ impl Direction {
     }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
 struct Pos {
     row: isize,
     col: isize,
@@ -287,7 +290,9 @@ impl Pos {
 
 #[cfg(test)]
 mod tests {
-    use crate::solve_10b;
+    use std::str::FromStr;
+
+    use crate::{interior::Method, render::Style, solve_10a, solve_10b, Maze, MazeError};
 
     #[test]
     fn test_10b() {
@@ -303,4 +308,92 @@ mod tests {
 
         assert_eq!(solve_10b(INPUT), 4)
     }
+
+    #[test]
+    fn test_10a() {
+        const INPUT: &str = "7-F7-
+.FJ|7
+SJLL7
+|F--J
+LJ.LJ";
+
+        assert_eq!(solve_10a(INPUT), 8)
+    }
+
+    #[test]
+    fn test_loop_at_edges() {
+        assert_eq!(solve_10a("F7\nLS"), 2);
+        assert_eq!(solve_10a(".F7\n.S|\n.LJ"), 3);
+    }
+
+    #[test]
+    fn test_malformed() {
+        let find_loop = |input| Maze::from_str(input).and_then(|mut maze| maze.find_loop());
+
+        assert_eq!(find_loop("..\n.."), Err(MazeError::MissingStart));
+        assert_eq!(find_loop("S-\n.."), Err(MazeError::NoLoop));
+        assert_eq!(find_loop("F7.\nLS7\n.LJ"), Err(MazeError::MultipleLoops));
+    }
+
+    #[test]
+    fn test_interior_methods() {
+        const INPUT: &str = "FF7FSF7F7F7F7F7F---7
+L|LJ||||||||||||F--J
+FL-7LJLJ||||||LJL-77
+F--JF--7||LJLJ7F7FJ-
+L---JF-JLJ.||-FJLJJ7
+|F|F-JF---7F7-L7L|7|
+|FFJF7L7F-JF7|JL---7
+7-L-JL7||F7|L7F-7F7|
+L.L7LFJ|||||FJL7||LJ
+L7JLJL-JLJLJL--JLJ.L";
+
+        let mut maze = Maze::from_str(INPUT).unwrap();
+        maze.find_loop().unwrap();
+        let scanline = maze.interior(Method::Scanline);
+
+        assert_eq!(scanline.len(), 10);
+        assert_eq!(maze.pick_count(), 10);
+        assert_eq!(maze.interior(Method::Polygon), scanline);
+        assert_eq!(maze.interior(Method::FloodFill), scanline);
+    }
+
+    #[test]
+    fn test_render() {
+        let mut maze = Maze::from_str(
+            "...........
+.S-------7.
+.|F-----7|.
+.||.....||.
+.||.....||.
+.|L-7.F-J|.
+.|..|.|..|.
+.L--J.L--J.
+...........",
+        )
+        .unwrap();
+        maze.find_loop().unwrap();
+
+        assert_eq!(
+            maze.render(Style::Ascii).to_string(),
+            "OOOOOOOOOOO
+OF-------7O
+O|F-----7|O
+O||OOOOO||O
+O||OOOOO||O
+O|L-7OF-J|O
+O|II|O|II|O
+OL--JOL--JO
+OOOOOOOOOOO
+"
+        );
+        assert!(maze
+            .render(Style::Ansi)
+            .to_string()
+            .contains("\x1b[2;32m·\x1b[0m"));
+
+        let mut maze = Maze::from_str("F7.\nLS-").unwrap();
+        maze.find_loop().unwrap();
+        assert_eq!(maze.to_string(), "┌┐O\n└┘O\n");
+    }
 }
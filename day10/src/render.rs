@@ -0,0 +1,122 @@
+use std::{
+    collections::BTreeSet,
+    fmt,
+    io::{self, IsTerminal},
+};
+
+use crate::{interior::Method, Maze, Pipe, Pos};
+
+const INTERIOR_COLOUR: u8 = 32;
+const EXTERIOR_COLOUR: u8 = 34;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Style {
+    /// The puzzle's own characters, with `I` and `O` for tiles inside and outside the loop.
+    Ascii,
+    /// Box-drawing characters for the loop, with `I` and `O` for the other tiles.
+    Unicode,
+    /// Box-drawing characters for the loop, with everything else dimmed and coloured by whether it
+    /// is inside or outside the loop.
+    Ansi,
+}
+
+impl Style {
+    /// Colours when writing to a terminal, plain ASCII otherwise.
+    pub fn detect() -> Self {
+        if io::stdout().is_terminal() {
+            Style::Ansi
+        } else {
+            Style::Ascii
+        }
+    }
+}
+
+pub struct Render<'a> {
+    maze: &'a Maze,
+    style: Style,
+    on_loop: BTreeSet<Pos>,
+    interior: BTreeSet<Pos>,
+}
+
+impl Maze {
+    pub fn render(&self, style: Style) -> Render<'_> {
+        Render {
+            maze: self,
+            style,
+            on_loop: self.path.iter().copied().collect(),
+            interior: self.interior(Method::Scanline),
+        }
+    }
+}
+
+impl fmt::Display for Render<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (row, pipes) in self.maze.pipes.iter().enumerate() {
+            for (col, pipe) in pipes.iter().enumerate() {
+                let pos = Pos {
+                    row: row as isize,
+                    col: col as isize,
+                };
+                let inside = self.interior.contains(&pos);
+
+                if self.on_loop.contains(&pos) {
+                    match self.style {
+                        Style::Ascii => write!(f, "{}", pipe.to_ascii())?,
+                        Style::Unicode | Style::Ansi => write!(f, "{}", pipe.to_box())?,
+                    }
+                } else {
+                    match self.style {
+                        Style::Ascii | Style::Unicode => {
+                            write!(f, "{}", if inside { 'I' } else { 'O' })?
+                        }
+                        Style::Ansi => {
+                            let colour = if inside {
+                                INTERIOR_COLOUR
+                            } else {
+                                EXTERIOR_COLOUR
+                            };
+                            write!(f, "\x1b[2;{colour}m{}\x1b[0m", pipe.to_box())?
+                        }
+                    }
+                }
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Maze {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.render(Style::Unicode).fmt(f)
+    }
+}
+
+impl Pipe {
+    fn to_ascii(self) -> char {
+        match self {
+            Pipe::UpDown => '|',
+            Pipe::LeftRight => '-',
+            Pipe::UpRight => 'L',
+            Pipe::UpLeft => 'J',
+            Pipe::DownLeft => '7',
+            Pipe::DownRight => 'F',
+            Pipe::Start => 'S',
+            Pipe::None => '.',
+        }
+    }
+
+    fn to_box(self) -> char {
+        match self {
+            Pipe::UpDown => '│',
+            Pipe::LeftRight => '─',
+            Pipe::UpRight => '└',
+            Pipe::UpLeft => '┘',
+            Pipe::DownLeft => '┐',
+            Pipe::DownRight => '┌',
+            Pipe::Start => 'S',
+            Pipe::None => '·',
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use std::{
-    collections::BTreeSet,
+    env,
     fmt::{Display, Formatter},
     str::FromStr,
 };
@@ -7,119 +7,178 @@ use std::{
 fn main() {
     let input = include_str!("../../inputs/input11");
 
-    println!("Part One: {}", solve_11a(input));
-    println!("Part Two: {}", solve_11b(input));
+    match env::args().nth(1).as_deref() {
+        Some("distance") => {
+            let args: Vec<usize> = env::args()
+                .skip(2)
+                .map(|arg| arg.parse().unwrap())
+                .collect();
+            let universe = Universe::from_str(input).unwrap();
+            let age = args.get(2).copied().unwrap_or(2) as u128;
+            println!("{}", universe.distance(args[0], args[1], age));
+        }
+        _ => {
+            println!("Part One: {}", solve_11a(input));
+            println!("Part Two: {}", solve_11b(input));
+        }
+    }
 }
 
-fn solve_11a(input: &str) -> u32 {
-    let input: Input = input.parse().unwrap();
-    let rows = input.empty_rows();
-    let cols = input.empty_columns();
-    input.summed_distances(&rows, &cols, 2) as u32
+fn solve_11a(input: &str) -> u128 {
+    let universe: Universe = input.parse().unwrap();
+    universe.summed_distances(2)
 }
 
-fn solve_11b(input: &str) -> u64 {
-    let input: Input = input.parse().unwrap();
-    let rows = input.empty_rows();
-    let cols = input.empty_columns();
-    input.summed_distances(&rows, &cols, 1000000)
+fn solve_11b(input: &str) -> u128 {
+    let universe: Universe = input.parse().unwrap();
+    universe.summed_distances(1000000)
 }
 
-struct Input {
-    galaxies: Vec<(usize, usize)>,
-    rows: Vec<Vec<Tile>>,
+/// The galaxies of a universe with any number of dimensions, split up into one coordinate per
+/// axis.
+struct Universe {
+    axes: Vec<Axis>,
 }
 
-impl Input {
-    fn summed_distances(&self, rows: &BTreeSet<usize>, cols: &BTreeSet<usize>, age: usize) -> u64 {
-        let mut galaxies = self.galaxies.clone();
-        let mut sum = 0;
+impl Universe {
+    /// Builds a universe from the coordinates of its galaxies, or `None` if they don't all have
+    /// the same number of dimensions.
+    fn from_galaxies(galaxies: impl IntoIterator<Item = Vec<usize>>) -> Option<Self> {
+        let mut axes: Option<Vec<Vec<usize>>> = None;
 
-        let mut current = galaxies.pop();
-        while let Some((row, col)) = current {
-            for &(row2, col2) in &galaxies {
-                let smallest_row = row.min(row2);
-                let biggest_row = row.max(row2);
-                let smallest_col = col.min(col2);
-                let biggest_col = col.max(col2);
-
-                for _ in rows
-                    .iter()
-                    .copied()
-                    .filter(|r| smallest_row <= *r && *r < biggest_row)
-                {
-                    sum += age - 1;
-                }
-                for _ in cols
-                    .iter()
-                    .copied()
-                    .filter(|r| smallest_col <= *r && *r < biggest_col)
-                {
-                    sum += age - 1;
-                }
-                sum += biggest_row - smallest_row + biggest_col - smallest_col;
+        for galaxy in galaxies {
+            let axes = axes.get_or_insert_with(|| vec![Vec::new(); galaxy.len()]);
+            if axes.len() != galaxy.len() {
+                return None;
+            }
+            for (axis, coordinate) in axes.iter_mut().zip(galaxy) {
+                axis.push(coordinate);
             }
-            current = galaxies.pop();
         }
 
-        sum as u64
+        Some(Universe {
+            axes: axes
+                .unwrap_or_default()
+                .into_iter()
+                .map(Axis::new)
+                .collect(),
+        })
+    }
+
+    fn len(&self) -> usize {
+        self.axes.first().map_or(0, |axis| axis.coordinates.len())
     }
 
-    fn empty_rows(&self) -> BTreeSet<usize> {
-        self.rows
+    /// The sum of the distances between every pair of galaxies, after every empty slice of the
+    /// universe has grown to `age` slices.
+    fn summed_distances(&self, age: u128) -> u128 {
+        self.axes
             .iter()
-            .enumerate()
-            .filter(|(_, row)| row.iter().all(|tile| *tile == Tile::Space))
-            .map(|(index, _)| index)
-            .collect()
+            .map(|axis| axis.summed_distances(age))
+            .sum()
     }
 
-    fn empty_columns(&self) -> BTreeSet<usize> {
-        let mut empty_columns = BTreeSet::new();
+    /// The distance between two galaxies, by their index in the input.
+    fn distance(&self, a: usize, b: usize, age: u128) -> u128 {
+        self.axes
+            .iter()
+            .map(|axis| {
+                axis.expand(axis.coordinates[a], age)
+                    .abs_diff(axis.expand(axis.coordinates[b], age))
+            })
+            .sum()
+    }
+}
 
-        for column_index in 0..self.rows[0].len() {
-            if self.rows.iter().all(|row| row[column_index] == Tile::Space) {
-                empty_columns.insert(column_index);
-            }
+/// The coordinates of all galaxies along one axis.
+struct Axis {
+    /// The coordinate of every galaxy, in input order.
+    coordinates: Vec<usize>,
+    /// Every coordinate that has at least one galaxy, sorted.
+    occupied: Vec<usize>,
+}
+
+impl Axis {
+    fn new(coordinates: Vec<usize>) -> Self {
+        let mut occupied = coordinates.clone();
+        occupied.sort_unstable();
+        occupied.dedup();
+
+        Axis {
+            coordinates,
+            occupied,
+        }
+    }
+
+    /// The coordinate after expansion. Every coordinate before this one without a galaxy counts
+    /// `age` times.
+    fn expand(&self, coordinate: usize, age: u128) -> u128 {
+        let empty_before = coordinate - self.occupied.partition_point(|&c| c < coordinate);
+        coordinate as u128 + empty_before as u128 * (age - 1)
+    }
+
+    fn summed_distances(&self, age: u128) -> u128 {
+        let mut expanded: Vec<_> = self
+            .coordinates
+            .iter()
+            .map(|&coordinate| self.expand(coordinate, age))
+            .collect();
+        expanded.sort_unstable();
+
+        // Every coordinate is subtracted from all larger ones, so each contributes to the sum
+        // once for every smaller coordinate, and negatively once for every larger one.
+        let mut sum = 0;
+        let mut prefix = 0;
+        for (index, coordinate) in expanded.into_iter().enumerate() {
+            sum += coordinate * index as u128 - prefix;
+            prefix += coordinate;
         }
 
-        empty_columns
+        sum
     }
 }
 
-impl FromStr for Input {
+/// Accepts either a grid of `.` and `#`, or a list of galaxies with one comma-separated list of
+/// coordinates per line. Anything that contains other characters is taken to be a list.
+impl FromStr for Universe {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut galaxies = Vec::new();
-        let rows = s
-            .lines()
-            .enumerate()
-            .map(|(row, line)| {
+        let is_grid = s.lines().flat_map(str::chars).all(|c| c == '.' || c == '#');
+
+        if is_grid {
+            let galaxies = s.lines().enumerate().flat_map(|(row, line)| {
                 line.chars()
                     .map(Tile::from_char)
                     .enumerate()
-                    .map(|(col, tile)| {
-                        if tile == Tile::Galaxy {
-                            galaxies.push((row, col))
-                        }
-                        tile
-                    })
-                    .collect()
-            })
-            .collect();
-
-        Ok(Input { galaxies, rows })
+                    .filter(|(_, tile)| *tile == Tile::Galaxy)
+                    .map(move |(col, _)| vec![row, col])
+            });
+            Universe::from_galaxies(galaxies).ok_or(())
+        } else {
+            let galaxies = s
+                .lines()
+                .map(|line| {
+                    line.split(',')
+                        .map(|coordinate| coordinate.trim().parse().map_err(|_| ()))
+                        .collect()
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Universe::from_galaxies(galaxies).ok_or(())
+        }
     }
 }
 
-impl Display for Input {
+/// Writes the galaxies in the coordinate list format.
+impl Display for Universe {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        for row in &self.rows {
-            for tile in row {
-                write!(f, "{tile}")?;
-            }
-            writeln!(f)?;
+        for galaxy in 0..self.len() {
+            let coordinates: Vec<_> = self
+                .axes
+                .iter()
+                .map(|axis| axis.coordinates[galaxy].to_string())
+                .collect();
+            writeln!(f, "{}", coordinates.join(","))?;
         }
         Ok(())
     }
@@ -141,22 +200,11 @@ impl Tile {
     }
 }
 
-impl Display for Tile {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Tile::Space => write!(f, "."),
-            Tile::Galaxy => write!(f, "#"),
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests {
-    use crate::solve_11a;
+    use crate::{solve_11a, Universe};
 
-    #[test]
-    fn test_11a() {
-        const INPUT: &str = "...#......
+    const INPUT: &str = "...#......
 .......#..
 #.........
 ..........
@@ -167,6 +215,58 @@ mod tests {
 .......#..
 #...#.....";
 
+    #[test]
+    fn test_11a() {
         assert_eq!(solve_11a(INPUT), 374)
     }
+
+    #[test]
+    fn test_11b() {
+        let universe = INPUT.parse::<Universe>().unwrap();
+
+        assert_eq!(universe.summed_distances(10), 1030);
+        assert_eq!(universe.summed_distances(100), 8410);
+    }
+
+    #[test]
+    fn test_distance() {
+        let universe = INPUT.parse::<Universe>().unwrap();
+
+        assert_eq!(universe.distance(4, 8, 2), 9);
+        assert_eq!(universe.distance(0, 6, 2), 15);
+        assert_eq!(universe.distance(2, 5, 2), 17);
+    }
+
+    #[test]
+    fn test_million_galaxies() {
+        // Galaxies on every other row, so row 2i ends up at (age + 1) * i, and the distances sum
+        // to (age + 1) * (n³ - n) / 6.
+        let n = 1_000_000_u128;
+        let universe = Universe::from_galaxies((0..n as usize).map(|i| vec![i * 2, 0])).unwrap();
+
+        assert_eq!(
+            universe.summed_distances(1_000_000),
+            1_000_001 * (n * n * n - n) / 6
+        );
+    }
+
+    #[test]
+    fn test_coordinate_list() {
+        let universe = INPUT.parse::<Universe>().unwrap();
+        let listed = universe.to_string();
+
+        assert!(listed.starts_with("0,3\n1,7\n2,0\n"));
+        assert_eq!(solve_11a(&listed), 374);
+
+        // Two galaxies with an empty slice between them along every axis.
+        let cube: Universe = "0,0,0\n2,2,2".parse().unwrap();
+        assert_eq!(cube.summed_distances(10), 33);
+
+        assert!("0,0\n1,2,3".parse::<Universe>().is_err());
+        assert!("0,0\n1,x".parse::<Universe>().is_err());
+
+        // A single dimension has no commas at all. Expanded, the galaxies are at 9, 12 and 0.
+        let line: Universe = "5\n7\n0".parse().unwrap();
+        assert_eq!(line.summed_distances(2), 3 + 9 + 12);
+    }
 }
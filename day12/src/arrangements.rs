@@ -0,0 +1,126 @@
+use rand::{Rng, RngExt};
+
+use crate::{Line, Spring, Table};
+
+/// Lazily yields every arrangement of the springs in a line, trying operational springs before
+/// broken ones.
+pub struct Arrangements<'a> {
+    table: Table<'a, bool>,
+    /// Partial arrangements still to be finished, with the spring and segment offsets they are at.
+    /// Only partial arrangements that can still be finished are pushed.
+    stack: Vec<(usize, usize, Vec<Spring>)>,
+}
+
+impl Iterator for Arrangements<'_> {
+    type Item = Vec<Spring>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((spring, segment, arrangement)) = self.stack.pop() {
+            if spring == self.table.line.springs.len() {
+                return Some(arrangement);
+            }
+
+            if let Some(next) = self.table.skip_broken(spring, segment) {
+                if self.table.get(next, segment + 1) {
+                    let mut arrangement = arrangement.clone();
+                    push_segment(
+                        &mut arrangement,
+                        self.table.line.segments[segment],
+                        next - spring,
+                    );
+                    self.stack.push((next, segment + 1, arrangement));
+                }
+            }
+            if let Some(next) = self.table.skip_operational(spring) {
+                if self.table.get(next, segment) {
+                    let mut arrangement = arrangement;
+                    arrangement.push(Spring::Operational);
+                    self.stack.push((next, segment, arrangement));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+impl Line {
+    pub fn arrangements(&self) -> Arrangements<'_> {
+        let table = Table::<bool>::new(self);
+        let stack = if table.get(0, 0) {
+            vec![(0, 0, Vec::new())]
+        } else {
+            Vec::new()
+        };
+
+        Arrangements { table, stack }
+    }
+
+    /// Picks one of the arrangements, each with the same probability, or `None` if there are none.
+    /// Also `None` if the arrangements after the first spring are too many to count in a `u128`.
+    pub fn sample(&self, rng: &mut impl Rng) -> Option<Vec<Spring>> {
+        let table = Table::<Option<u128>>::new(self);
+        if table.get(0, 0) == Some(0) {
+            return None;
+        }
+
+        let mut arrangement = Vec::new();
+        let (mut spring, mut segment) = (0, 0);
+        while spring < self.springs.len() {
+            let operational = table.skip_operational(spring);
+            let broken = table.skip_broken(spring, segment);
+            let count = |next: Option<usize>, segment| {
+                next.map_or(Some(0), |next| table.get(next, segment))
+            };
+            if choose_first(
+                rng,
+                count(operational, segment)?,
+                count(broken, segment + 1)?,
+            ) {
+                arrangement.push(Spring::Operational);
+                spring += 1;
+            } else {
+                let next = broken.unwrap();
+                push_segment(&mut arrangement, self.segments[segment], next - spring);
+                spring = next;
+                segment += 1;
+            }
+        }
+
+        Some(arrangement)
+    }
+}
+
+/// Chooses between two options in proportion to how many arrangements each leads to, even if
+/// their sum doesn't fit in a `u128`.
+fn choose_first(rng: &mut impl Rng, first: u128, second: u128) -> bool {
+    match first.checked_add(second) {
+        Some(total) => rng.random_range(0..total) < first,
+        // Picks a number below 2^129 from a random top bit and the 128 bits below it, until it is
+        // below the sum. Without the top bit it always is, as the sum is at least 2^128.
+        None => loop {
+            let (top, rest) = (rng.random::<bool>(), rng.random::<u128>());
+            if !top {
+                return rest < first;
+            } else if rest < first.wrapping_add(second) {
+                return false;
+            }
+        },
+    }
+}
+
+/// Renders springs in the notation of the puzzle input.
+pub fn render(springs: &[Spring]) -> String {
+    springs.iter().map(|&spring| char::from(spring)).collect()
+}
+
+/// Adds a segment of broken springs, followed by operational springs up to `width`.
+fn push_segment(arrangement: &mut Vec<Spring>, length: u32, width: usize) {
+    arrangement.extend((0..width).map(|index| {
+        if index < length as usize {
+            Spring::Broken
+        } else {
+            Spring::Operational
+        }
+    }));
+}
@@ -1,121 +1,246 @@
-use std::{str::FromStr, time::Instant};
+mod arrangements;
+mod nonogram;
+mod unfold;
 
-use cached::proc_macro::cached;
+use std::{env, fs, str::FromStr, time::Instant};
+
+use crate::{
+    arrangements::render,
+    nonogram::{render_grid, Nonogram, Solution},
+    unfold::DIRECT_LIMIT,
+};
 
 fn main() {
     let input = include_str!("../../inputs/input12");
 
+    match env::args().nth(1).as_deref() {
+        Some("show") => {
+            let args: Vec<usize> = env::args()
+                .skip(2)
+                .map(|arg| arg.parse().unwrap())
+                .collect();
+            let line = Line::from_str(input.lines().nth(args[0]).unwrap()).unwrap();
+            let limit = args.get(1).copied().unwrap_or(usize::MAX);
+            println!("{}", render(&line.springs));
+            for arrangement in line.arrangements().take(limit) {
+                println!("{}", render(&arrangement));
+            }
+            return;
+        }
+        Some("sample") => {
+            let index = env::args().nth(2).unwrap().parse().unwrap();
+            let line = Line::from_str(input.lines().nth(index).unwrap()).unwrap();
+            println!("{}", render(&line.springs));
+            println!("{}", render(&line.sample(&mut rand::rng()).unwrap()));
+            return;
+        }
+        Some("unfold") => {
+            let args: Vec<String> = env::args().skip(2).collect();
+            let repetitions = args[0].parse().unwrap();
+            let separator: Vec<_> = args
+                .get(1)
+                .map_or("?", |s| s)
+                .chars()
+                .map(Spring::from)
+                .collect();
+            let modulus = args.get(2).map_or(1_000_000_007, |m| m.parse().unwrap());
+
+            let mut total = 0;
+            let mut unsupported = Vec::new();
+            for (index, line) in input.lines().enumerate() {
+                let line = Line::from_str(line).unwrap();
+                match line.unfolded_possibilities_mod(repetitions, &separator, modulus) {
+                    Some(count) => {
+                        println!("{index}: {count}");
+                        total = (total + count) % modulus;
+                    }
+                    None => {
+                        println!("{index}: unsupported, its segments can drift between copies");
+                        unsupported.push(index);
+                    }
+                }
+            }
+            if unsupported.is_empty() {
+                println!("Total: {total} (mod {modulus})");
+            } else {
+                println!(
+                    "Partial total without lines {unsupported:?}: {total} (mod {modulus}), as \
+                     their drift is unbounded and there are more than {DIRECT_LIMIT} repetitions"
+                );
+            }
+            return;
+        }
+        Some("nonogram") => {
+            let clues = fs::read_to_string(env::args().nth(2).unwrap()).unwrap();
+            match Nonogram::from_str(&clues).unwrap().solve() {
+                Solution::Unique(grid) => print!("Unique solution:\n{}", render_grid(&grid)),
+                Solution::Multiple(first, second) => print!(
+                    "Multiple solutions, including:\n{}\n{}",
+                    render_grid(&first),
+                    render_grid(&second)
+                ),
+                Solution::None => println!("No solution"),
+            }
+            return;
+        }
+        _ => (),
+    }
+
     let start = Instant::now();
     println!(
         "Part One: {} ({}µs)",
-        solve_12a(input),
+        show(solve_12a(input)),
         start.elapsed().as_micros()
     );
 
     let start = Instant::now();
     println!(
         "Part Two: {} ({}µs)",
-        solve_12b(input),
+        show(solve_12b(input)),
         start.elapsed().as_micros()
     );
 }
 
-fn solve_12a(input: &str) -> u64 {
-    let lines: Vec<_> = input
+fn solve_12a(input: &str) -> Option<u64> {
+    input
         .lines()
         .map(|line| Line::from_str(line).unwrap())
-        .collect();
-
-    lines
-        .into_iter()
-        .map(|line| Box::leak(Box::new(line)))
-        .map(|line| possibilities(&line.springs, &line.segments))
-        .sum()
+        .map(|line| line.possibilities())
+        .try_fold(0_u64, |total, count| total.checked_add(count?))
 }
 
-fn solve_12b(input: &str) -> u64 {
-    let lines: Vec<_> = input
+fn solve_12b(input: &str) -> Option<u64> {
+    input
         .lines()
         .map(|line| Line::from_str(line).unwrap())
-        .map(|line| line.multiplied(5))
-        .collect();
-
-    lines
-        .into_iter()
-        .map(|line| Box::leak(Box::new(line)))
-        .map(|line| possibilities(&line.springs, &line.segments))
-        .sum()
-}
-
-#[cached]
-fn possibilities(springs: &'static [Spring], segments: &'static [u32]) -> u64 {
-    match springs.first() {
-        None => {
-            if segments.is_empty() {
-                1
-            } else {
-                0
-            }
-        }
-        Some(Spring::Operational) => possibilities_first_operational(springs, segments),
-        Some(Spring::Broken) => possibilities_first_broken(springs, segments),
-        Some(Spring::Unknown) => {
-            let operational = possibilities_first_operational(springs, segments);
-            let broken = possibilities_first_broken(springs, segments);
-            operational + broken
-        }
+        .map(|line| line.unfolded(5, &[Spring::Unknown]))
+        .map(|line| line.possibilities())
+        .try_fold(0_u64, |total, count| total.checked_add(count?))
+}
+
+fn show(count: Option<u64>) -> String {
+    count.map_or("more than fits in a u64".to_owned(), |count| {
+        count.to_string()
+    })
+}
+
+struct Line {
+    springs: Vec<Spring>,
+    segments: Vec<u32>,
+}
+
+impl Line {
+    /// The number of arrangements, or `None` if there are more than fit in a `u64`.
+    fn possibilities(&self) -> Option<u64> {
+        Table::new(self).get(0, 0)
     }
 }
 
-fn possibilities_first_broken(springs: &'static [Spring], segments: &'static [u32]) -> u64 {
-    match segments.first() {
-        None => 0,
-        Some(&segment) => {
-            if springs.len() >= segment as usize
-                && springs
-                    .iter()
-                    .take(segment as usize)
-                    .all(Spring::might_be_broken)
-            {
-                if springs.len() == segment as usize {
-                    if segments.len() == 1 {
-                        1
-                    } else {
-                        0
-                    }
-                } else if springs[segment as usize].might_be_operational() {
-                    possibilities(&springs[segment as usize + 1..], &segments[1..])
-                } else {
-                    0
+/// The number of arrangements of the springs from some offset onwards that match the segments
+/// from some offset onwards, for every pair of offsets. Counts that don't fit are `None`, and with
+/// `bool` it only tracks whether there are any.
+struct Table<'a, C = Option<u64>> {
+    line: &'a Line,
+    counts: Vec<Vec<C>>,
+    /// How many springs, starting at each offset, might all be broken.
+    broken_run: Vec<usize>,
+}
+
+impl<'a, C: Count> Table<'a, C> {
+    fn new(line: &'a Line) -> Self {
+        let springs = line.springs.len();
+        let segments = line.segments.len();
+
+        let mut broken_run = vec![0; springs + 1];
+        for spring in (0..springs).rev() {
+            if line.springs[spring].might_be_broken() {
+                broken_run[spring] = broken_run[spring + 1] + 1;
+            }
+        }
+
+        let mut table = Table {
+            line,
+            counts: vec![vec![C::ZERO; segments + 1]; springs + 1],
+            broken_run,
+        };
+        table.counts[springs][segments] = C::ONE;
+
+        for spring in (0..springs).rev() {
+            for segment in 0..=segments {
+                let mut count = C::ZERO;
+                if let Some(next) = table.skip_operational(spring) {
+                    count = count.add(table.get(next, segment));
                 }
-            } else {
-                0
+                if let Some(next) = table.skip_broken(spring, segment) {
+                    count = count.add(table.get(next, segment + 1));
+                }
+                table.counts[spring][segment] = count;
             }
         }
+
+        table
+    }
+
+    fn get(&self, spring: usize, segment: usize) -> C {
+        self.counts[spring][segment]
+    }
+
+    /// The next spring offset, if the spring at `spring` might be operational.
+    fn skip_operational(&self, spring: usize) -> Option<usize> {
+        self.line.springs[spring]
+            .might_be_operational()
+            .then_some(spring + 1)
+    }
+
+    /// The next spring offset, if the segment at `segment` might start at `spring`. This includes
+    /// the operational spring needed to separate it from the next segment.
+    fn skip_broken(&self, spring: usize, segment: usize) -> Option<usize> {
+        let length = *self.line.segments.get(segment)? as usize;
+        let end = spring + length;
+
+        if self.broken_run[spring] < length {
+            None
+        } else if end == self.line.springs.len() {
+            Some(end)
+        } else {
+            self.line.springs[end]
+                .might_be_operational()
+                .then_some(end + 1)
+        }
     }
 }
 
-fn possibilities_first_operational(springs: &'static [Spring], segments: &'static [u32]) -> u64 {
-    possibilities(&springs[1..], segments)
+trait Count: Copy {
+    const ZERO: Self;
+    const ONE: Self;
+
+    fn add(self, other: Self) -> Self;
 }
 
-struct Line {
-    springs: Vec<Spring>,
-    segments: Vec<u32>,
+impl Count for Option<u64> {
+    const ZERO: Self = Some(0);
+    const ONE: Self = Some(1);
+
+    fn add(self, other: Self) -> Self {
+        self?.checked_add(other?)
+    }
 }
 
-impl Line {
-    fn multiplied(mut self, multiplier: u32) -> Self {
-        let original_springs = self.springs.clone();
-        let original_segments = self.segments.clone();
-
-        for _ in 0..multiplier - 1 {
-            self.springs.push(Spring::Unknown);
-            self.springs.extend(original_springs.clone());
-            self.segments.extend(original_segments.clone());
-        }
+impl Count for Option<u128> {
+    const ZERO: Self = Some(0);
+    const ONE: Self = Some(1);
+
+    fn add(self, other: Self) -> Self {
+        self?.checked_add(other?)
+    }
+}
 
-        self
+impl Count for bool {
+    const ZERO: Self = false;
+    const ONE: Self = true;
+
+    fn add(self, other: Self) -> Self {
+        self || other
     }
 }
 
@@ -131,8 +256,8 @@ impl FromStr for Line {
     }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
-enum Spring {
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Spring {
     Operational,
     Broken,
     Unknown,
@@ -148,6 +273,16 @@ impl Spring {
     }
 }
 
+impl From<Spring> for char {
+    fn from(spring: Spring) -> Self {
+        match spring {
+            Spring::Operational => '.',
+            Spring::Broken => '#',
+            Spring::Unknown => '?',
+        }
+    }
+}
+
 impl From<char> for Spring {
     fn from(c: char) -> Self {
         match c {
@@ -161,7 +296,15 @@ impl From<char> for Spring {
 
 #[cfg(test)]
 mod tests {
-    use crate::{solve_12a, solve_12b};
+    use std::{collections::BTreeMap, str::FromStr};
+
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::{
+        arrangements::render,
+        nonogram::{render_grid, Nonogram, Solution},
+        solve_12a, solve_12b, Line, Spring,
+    };
 
     const INPUT: &str = "???.### 1,1,3
 .??..??...?##. 1,1,3
@@ -173,11 +316,205 @@ mod tests {
 
     #[test]
     fn test_12a() {
-        assert_eq!(solve_12a(INPUT), 21)
+        assert_eq!(solve_12a(INPUT), Some(21))
     }
 
     #[test]
     fn test_12b() {
-        assert_eq!(solve_12b(INPUT), 525152)
+        assert_eq!(solve_12b(INPUT), Some(525152))
+    }
+
+    #[test]
+    fn test_edge_cases() {
+        let possibilities = |line: &str| Line::from_str(line).unwrap().possibilities();
+
+        assert_eq!(possibilities("# 1"), Some(1));
+        assert_eq!(possibilities("? 2"), Some(0));
+        assert_eq!(possibilities("### 1,1"), Some(0));
+        assert_eq!(possibilities("????? 1,1"), Some(6));
+        assert_eq!(possibilities("#.# 1"), Some(0));
+    }
+
+    #[test]
+    fn test_arrangements() {
+        let line = Line::from_str("?###???????? 3,2,1").unwrap();
+        let arrangements: Vec<_> = line.arrangements().map(|a| render(&a)).collect();
+
+        assert_eq!(arrangements.len(), 10);
+        assert_eq!(arrangements[0], ".###....##.#");
+        assert_eq!(arrangements[9], ".###.##.#...");
+        assert_eq!(line.arrangements().take(3).count(), 3);
+        assert_eq!(Line::from_str("# 2").unwrap().arrangements().next(), None);
+    }
+
+    #[test]
+    fn test_sample() {
+        let line = Line::from_str("????? 1,1").unwrap();
+        let mut rng = StdRng::seed_from_u64(12);
+        let mut seen = BTreeMap::new();
+
+        for _ in 0..6000 {
+            let arrangement = render(&line.sample(&mut rng).unwrap());
+            *seen.entry(arrangement).or_insert(0) += 1;
+        }
+
+        // All six arrangements show up about equally often.
+        assert_eq!(seen.len(), 6);
+        assert!(seen.values().all(|&count| (800..1200).contains(&count)));
+        assert!(seen
+            .keys()
+            .all(|a| line.arrangements().any(|b| render(&b) == *a)));
+    }
+
+    #[test]
+    fn test_nonogram() {
+        const HEART: &str = "rows:
+1,1
+5
+5
+3
+1
+columns:
+2
+4
+4
+4
+2
+";
+        let nonogram = Nonogram::from_str(HEART).unwrap();
+        assert_eq!(nonogram.to_string(), HEART);
+
+        match nonogram.solve() {
+            Solution::Unique(grid) => {
+                assert_eq!(render_grid(&grid), ".#.#.\n#####\n#####\n.###.\n..#..\n")
+            }
+            solution => panic!("expected a unique solution, got {solution:?}"),
+        }
+
+        let ambiguous = Nonogram::from_str("rows:\n1\n1\ncolumns:\n1\n1").unwrap();
+        assert!(matches!(ambiguous.solve(), Solution::Multiple(_, _)));
+
+        let impossible = Nonogram::from_str("rows:\n2\n0\ncolumns:\n1\n0").unwrap();
+        assert_eq!(impossible.solve(), Solution::None);
+    }
+
+    #[test]
+    fn test_forced() {
+        let line = Line::from_str("??????? 4,1").unwrap();
+        assert_eq!(render(&line.forced().unwrap()), "?###???");
+        assert_eq!(Line::from_str("#.# 3").unwrap().forced(), None);
+
+        // Forced springs are exactly those that are the same in every arrangement.
+        for line in INPUT
+            .lines()
+            .chain(["?#?#?#?#?#?#?#? 1,3,1,6", "??.??#??? 2,3"])
+        {
+            let line = Line::from_str(line).unwrap();
+            let arrangements: Vec<_> = line.arrangements().collect();
+            let expected: Vec<_> = (0..line.springs.len())
+                .map(|index| {
+                    let first = arrangements[0][index];
+                    if arrangements
+                        .iter()
+                        .all(|arrangement| arrangement[index] == first)
+                    {
+                        first
+                    } else {
+                        Spring::Unknown
+                    }
+                })
+                .collect();
+            assert_eq!(line.forced(), Some(expected));
+        }
+
+        // Far more arrangements than fit in a u64, but only whether there are any matters.
+        let long = Line::from_str(&format!("{} {}", "?".repeat(100), ["1"; 30].join(","))).unwrap();
+        assert_eq!(long.forced(), Some(vec![Spring::Unknown; 100]));
+    }
+
+    #[test]
+    fn test_overflow() {
+        let ones = |springs| {
+            Line::from_str(&format!("{} {}", "?".repeat(springs), ["1"; 30].join(",")))
+                .unwrap()
+                .possibilities()
+        };
+        // 71 choose 30 is too many for a u64, 60 choose 30 is not.
+        assert_eq!(ones(100), None);
+        assert_eq!(ones(89), Some(118264581564861424));
+
+        // Too many to count in a u128, but not when split on the first spring.
+        let line = Line::from_str(&format!("{} {}", "?".repeat(189), ["1"; 52].join(","))).unwrap();
+        let mut rng = StdRng::seed_from_u64(12);
+        let arrangement = line.sample(&mut rng).unwrap();
+        assert_eq!(arrangement.len(), 189);
+        assert_eq!(
+            arrangement
+                .iter()
+                .filter(|&&spring| spring == Spring::Broken)
+                .count(),
+            52
+        );
+        assert!(!arrangement
+            .windows(2)
+            .any(|pair| pair == [Spring::Broken, Spring::Broken]));
+    }
+
+    #[test]
+    fn test_unfolded_mod() {
+        const MODULUS: u64 = 1_000_000_007;
+
+        for (line, unfolded_five_times) in INPUT.lines().zip([1, 16384, 1, 16, 2500, 506250]) {
+            let line = Line::from_str(line).unwrap();
+            for repetitions in 1..=5 {
+                assert_eq!(
+                    line.unfolded_possibilities_mod(repetitions, &[Spring::Unknown], MODULUS),
+                    line.unfolded(repetitions as usize, &[Spring::Unknown])
+                        .possibilities()
+                );
+            }
+            assert_eq!(
+                line.unfolded_possibilities_mod(5, &[Spring::Unknown], MODULUS),
+                Some(unfolded_five_times)
+            );
+        }
+
+        // Other separators work the same way.
+        let line = Line::from_str("#? 1").unwrap();
+        let separator = [Spring::Operational, Spring::Unknown];
+        assert_eq!(line.unfolded(3, &separator).possibilities(), Some(1));
+        assert_eq!(
+            line.unfolded_possibilities_mod(3, &separator, MODULUS),
+            Some(1)
+        );
+
+        // Every copy of this line has ten arrangements of its own, and fifteen when combined with
+        // the separator before it.
+        let line = Line::from_str("?###???????? 3,2,1").unwrap();
+        let mut expected = 10;
+        let mut base = 15;
+        let mut exponent = 999_999_999_u64;
+        while exponent > 0 {
+            if exponent % 2 == 1 {
+                expected = expected * base % MODULUS;
+            }
+            base = base * base % MODULUS;
+            exponent /= 2;
+        }
+        assert_eq!(
+            line.unfolded_possibilities_mod(1_000_000_000, &[Spring::Unknown], MODULUS),
+            Some(expected)
+        );
+
+        // C(3k, k) arrangements, which needs the drift in the state.
+        let unbounded = Line::from_str("??? 1").unwrap();
+        assert_eq!(
+            unbounded.unfolded_possibilities_mod(10, &[Spring::Unknown], MODULUS),
+            Some(30045015)
+        );
+        assert_eq!(
+            unbounded.unfolded_possibilities_mod(1_000_000_000, &[Spring::Unknown], MODULUS),
+            None
+        );
     }
 }
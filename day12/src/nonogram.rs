@@ -0,0 +1,217 @@
+use std::{fmt, str::FromStr};
+
+use crate::{arrangements::render, Line, Spring, Table};
+
+pub type Grid = Vec<Vec<Spring>>;
+
+/// A puzzle where every row and column is a day 12 line that starts out completely unknown.
+#[derive(Debug, Eq, PartialEq)]
+pub struct Nonogram {
+    rows: Vec<Vec<u32>>,
+    columns: Vec<Vec<u32>>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum Solution {
+    Unique(Grid),
+    /// Two of the solutions, there may be more.
+    Multiple(Grid, Grid),
+    None,
+}
+
+impl Nonogram {
+    pub fn solve(&self) -> Solution {
+        let grid = vec![vec![Spring::Unknown; self.columns.len()]; self.rows.len()];
+        let mut solutions = Vec::new();
+        self.search(grid, &mut solutions);
+
+        let mut solutions = solutions.into_iter();
+        match (solutions.next(), solutions.next()) {
+            (None, _) => Solution::None,
+            (Some(solution), None) => Solution::Unique(solution),
+            (Some(first), Some(second)) => Solution::Multiple(first, second),
+        }
+    }
+
+    /// Collects up to two solutions, guessing a cell whenever propagation gets stuck.
+    fn search(&self, mut grid: Grid, solutions: &mut Vec<Grid>) {
+        if solutions.len() >= 2 || !self.propagate(&mut grid) {
+            return;
+        }
+
+        let unknown = grid.iter().enumerate().find_map(|(row, springs)| {
+            springs
+                .iter()
+                .position(|&spring| spring == Spring::Unknown)
+                .map(|col| (row, col))
+        });
+
+        match unknown {
+            None => solutions.push(grid),
+            Some((row, col)) => {
+                for guess in [Spring::Broken, Spring::Operational] {
+                    let mut guessed = grid.clone();
+                    guessed[row][col] = guess;
+                    self.search(guessed, solutions);
+                }
+            }
+        }
+    }
+
+    /// Fixes every cell that is the same in all arrangements of its row or column, until nothing
+    /// changes anymore. Returns `false` if some row or column cannot be completed.
+    fn propagate(&self, grid: &mut Grid) -> bool {
+        let mut changed = true;
+
+        while changed {
+            changed = false;
+
+            for (row, segments) in self.rows.iter().enumerate() {
+                let line = Line {
+                    springs: grid[row].clone(),
+                    segments: segments.clone(),
+                };
+                match line.forced() {
+                    None => return false,
+                    Some(forced) => {
+                        changed |= forced != grid[row];
+                        grid[row] = forced;
+                    }
+                }
+            }
+
+            for (col, segments) in self.columns.iter().enumerate() {
+                let line = Line {
+                    springs: grid.iter().map(|springs| springs[col]).collect(),
+                    segments: segments.clone(),
+                };
+                match line.forced() {
+                    None => return false,
+                    Some(forced) => {
+                        for (springs, spring) in grid.iter_mut().zip(forced) {
+                            changed |= springs[col] != spring;
+                            springs[col] = spring;
+                        }
+                    }
+                }
+            }
+        }
+
+        true
+    }
+}
+
+impl Line {
+    /// The springs with every unknown spring replaced if it is the same in every arrangement, or
+    /// `None` if there are no arrangements at all.
+    pub fn forced(&self) -> Option<Vec<Spring>> {
+        // Whether the rest of the line can be finished from every pair of offsets.
+        let table = Table::<bool>::new(self);
+        if !table.get(0, 0) {
+            return None;
+        }
+
+        // Walk forwards through every pair of offsets that can be reached from the start, and
+        // mark what each spring might be along every step that can still be finished.
+        let springs = self.springs.len();
+        let segments = self.segments.len();
+        let mut reachable = vec![vec![false; segments + 1]; springs + 1];
+        reachable[0][0] = true;
+        let mut might_be_operational = vec![false; springs];
+        // Counts the broken segments that might start at each spring, minus those that might end.
+        let mut broken_starts = vec![0_i32; springs + 1];
+
+        for spring in 0..springs {
+            for segment in 0..=segments {
+                if !reachable[spring][segment] {
+                    continue;
+                }
+                if let Some(next) = table.skip_operational(spring) {
+                    if table.get(next, segment) {
+                        reachable[next][segment] = true;
+                        might_be_operational[spring] = true;
+                    }
+                }
+                if let Some(next) = table.skip_broken(spring, segment) {
+                    if table.get(next, segment + 1) {
+                        reachable[next][segment + 1] = true;
+                        let end = spring + self.segments[segment] as usize;
+                        broken_starts[spring] += 1;
+                        broken_starts[end] -= 1;
+                        if end < next {
+                            might_be_operational[end] = true;
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut forced = self.springs.clone();
+        let mut segments_here = 0;
+        for (index, spring) in forced.iter_mut().enumerate() {
+            segments_here += broken_starts[index];
+            match (segments_here > 0, might_be_operational[index]) {
+                (true, false) => *spring = Spring::Broken,
+                (false, true) => *spring = Spring::Operational,
+                _ => (),
+            }
+        }
+
+        Some(forced)
+    }
+}
+
+/// Expects a `rows:` and a `columns:` section, with one comma-separated clue per line, where `0`
+/// is an empty row or column.
+impl FromStr for Nonogram {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().strip_prefix("rows:\n").ok_or(())?;
+        let (rows, columns) = s.split_once("columns:").ok_or(())?;
+
+        let parse = |section: &str| -> Vec<Vec<u32>> {
+            section
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(|line| {
+                    line.split(',')
+                        .map(|n| n.parse().unwrap())
+                        .filter(|&n| n != 0)
+                        .collect()
+                })
+                .collect()
+        };
+
+        Ok(Nonogram {
+            rows: parse(rows),
+            columns: parse(columns),
+        })
+    }
+}
+
+impl fmt::Display for Nonogram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let write_clues = |f: &mut fmt::Formatter<'_>, clues: &[Vec<u32>]| -> fmt::Result {
+            for clue in clues {
+                if clue.is_empty() {
+                    writeln!(f, "0")?;
+                } else {
+                    let numbers: Vec<_> = clue.iter().map(u32::to_string).collect();
+                    writeln!(f, "{}", numbers.join(","))?;
+                }
+            }
+            Ok(())
+        };
+
+        writeln!(f, "rows:")?;
+        write_clues(f, &self.rows)?;
+        writeln!(f, "columns:")?;
+        write_clues(f, &self.columns)
+    }
+}
+
+pub fn render_grid(grid: &Grid) -> String {
+    grid.iter().map(|row| render(row) + "\n").collect()
+}
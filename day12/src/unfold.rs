@@ -0,0 +1,391 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    mem,
+};
+
+use crate::{Line, Spring};
+
+impl Line {
+    /// The line repeated `repetitions` times, with `separator` between every two copies.
+    pub fn unfolded(&self, repetitions: usize, separator: &[Spring]) -> Line {
+        let mut springs = self.springs.clone();
+        for _ in 1..repetitions {
+            springs.extend_from_slice(separator);
+            springs.extend_from_slice(&self.springs);
+        }
+
+        Line {
+            springs,
+            segments: self.segments.repeat(repetitions),
+        }
+    }
+
+    /// The number of arrangements of the unfolded line modulo `modulus`, without building it.
+    ///
+    /// Reading the unfolded line copy by copy, the state after each copy is where the automaton
+    /// is within the segments, plus the drift: how many more segments have been finished than the
+    /// copies so far hold. When the drift only depends on the automaton state, which is the case
+    /// when every copy's segments have to stay near its own springs, the count is a power of a
+    /// fixed transfer matrix, and any number of repetitions works.
+    ///
+    /// Otherwise, the drift can grow with the number of copies. In `??? 1` with a `?` separator,
+    /// every arrangement of `k` copies picks `k` of the `3k` springs that are left after placing
+    /// the mandatory gaps, so there are `C(3k, k)` of them. That grows like `(27/4)^k / sqrt(k)`,
+    /// and no fixed matrix power grows like that, so no finite set of states can work for lines
+    /// like this. These are counted one copy at a time with the drift in the state instead, which
+    /// takes time quadratic in `repetitions`, so this returns `None` above `DIRECT_LIMIT`.
+    pub fn unfolded_possibilities_mod(
+        &self,
+        repetitions: u64,
+        separator: &[Spring],
+        modulus: u64,
+    ) -> Option<u64> {
+        if repetitions == 0 {
+            return Some(1 % modulus);
+        }
+        if self.segments.is_empty() {
+            let broken = self.springs.contains(&Spring::Broken)
+                || repetitions > 1 && separator.contains(&Spring::Broken);
+            return Some(if broken { 0 } else { 1 % modulus });
+        }
+
+        let automaton = Automaton::new(&self.segments);
+        let states = automaton.len();
+
+        // Every copy after the first adds a separator and the springs of the line.
+        let chunk: Vec<_> = separator.iter().chain(&self.springs).copied().collect();
+        let initial = automaton.run(0, &self.springs, modulus);
+        let edges: Vec<_> = (0..states)
+            .map(|state| automaton.run(state, &chunk, modulus))
+            .collect();
+
+        transfer_matrix(&automaton, &initial, &edges, repetitions, modulus).or_else(|| {
+            (repetitions <= DIRECT_LIMIT)
+                .then(|| copy_by_copy(&automaton, initial, &edges, repetitions, modulus))
+        })
+    }
+}
+
+/// The most repetitions to count one copy at a time, for lines whose drift is unbounded. At this
+/// many, a line takes about a second.
+pub const DIRECT_LIMIT: u64 = 2_000;
+
+type Edges = Vec<HashMap<(usize, i64), u64>>;
+
+/// Counts with a matrix power, if the drift only depends on the automaton state.
+fn transfer_matrix(
+    automaton: &Automaton,
+    initial: &HashMap<(usize, i64), u64>,
+    edges: &Edges,
+    repetitions: u64,
+    modulus: u64,
+) -> Option<u64> {
+    let states = automaton.len();
+
+    // It can then be left out of the matrix, and checked at the end.
+    let useful = useful_states(automaton, initial, edges);
+    let potential = potential(&useful, edges)?;
+
+    let mut offsets = HashMap::new();
+    for (&(state, drift), _) in initial.iter().filter(|(&(s, _), _)| useful[s]) {
+        let (component, phi) = potential[state].unwrap();
+        if *offsets.entry(component).or_insert(drift - phi) != drift - phi {
+            return None;
+        }
+    }
+
+    let mut matrix = vec![vec![0; states]; states];
+    for (from, targets) in edges.iter().enumerate().filter(|(s, _)| useful[*s]) {
+        for (&(to, _), &count) in targets.iter().filter(|(&(t, _), _)| useful[t]) {
+            matrix[from][to] = (matrix[from][to] + count) % modulus;
+        }
+    }
+    let power = matrix_pow(matrix, repetitions - 1, modulus);
+
+    let mut total = 0;
+    for (&(from, _), &count) in initial.iter().filter(|(&(s, _), _)| useful[s]) {
+        let (component, _) = potential[from].unwrap();
+        for to in (0..states).filter(|&to| useful[to]) {
+            let (to_component, phi) = potential[to].unwrap();
+            if to_component != component {
+                continue;
+            }
+            let accepted = automaton
+                .accepting_drift(to)
+                .is_some_and(|drift| offsets[&component] + phi == drift);
+            if accepted {
+                total = (total + mul_mod(count, power[from][to], modulus)) % modulus;
+            }
+        }
+    }
+
+    Some(total)
+}
+
+/// Counts by following the automaton state and the drift through every copy.
+fn copy_by_copy(
+    automaton: &Automaton,
+    initial: HashMap<(usize, i64), u64>,
+    edges: &Edges,
+    repetitions: u64,
+    modulus: u64,
+) -> u64 {
+    let edges: Vec<Vec<_>> = edges
+        .iter()
+        .map(|targets| {
+            let mut targets: Vec<_> = targets
+                .iter()
+                .map(|(&(to, delta), &ways)| (to, delta, ways))
+                .collect();
+            targets.sort_unstable();
+            targets
+        })
+        .collect();
+    let deltas = edges.iter().flatten().map(|&(_, delta, _)| delta);
+    let (lowest, highest) = deltas.fold((0, 0), |(low, high), d| (low.min(d), high.max(d)));
+
+    // The drift has to end up at zero or minus one, so it never needs to leave these bounds.
+    let copies = repetitions as i64 - 1;
+    let min = -1 - copies * highest;
+    let max = -copies * lowest;
+    let index = |drift: i64| (drift - min) as usize;
+
+    let mut current = vec![vec![0; index(max) + 1]; automaton.len()];
+    // The drifts that have been reached so far, as counts outside these are all zero.
+    let (mut reached_low, mut reached_high) = (max, min);
+    for ((state, drift), count) in initial {
+        if (min..=max).contains(&drift) {
+            current[state][index(drift)] = count;
+            reached_low = reached_low.min(drift);
+            reached_high = reached_high.max(drift);
+        }
+    }
+
+    let mut next = current.clone();
+    for copy in 1..=copies {
+        // Skip drifts that can't get back with the copies that are left.
+        let remaining = copies - copy;
+        let (low, high) = (-1 - remaining * highest, -remaining * lowest);
+
+        next.iter_mut().for_each(|counts| counts.fill(0));
+        for (state, counts) in current.iter().enumerate() {
+            for drift in reached_low..=reached_high {
+                let count = counts[index(drift)];
+                if count == 0 {
+                    continue;
+                }
+                for &(to, delta, ways) in &edges[state] {
+                    let drift = drift + delta;
+                    if (low..=high).contains(&drift) {
+                        let entry = &mut next[to][index(drift)];
+                        *entry = (*entry + mul_mod(count, ways, modulus)) % modulus;
+                    }
+                }
+            }
+        }
+        mem::swap(&mut current, &mut next);
+        reached_low = (reached_low + lowest).max(low);
+        reached_high = (reached_high + highest).min(high);
+    }
+
+    (0..automaton.len())
+        .filter_map(|state| {
+            let drift = automaton.accepting_drift(state)?;
+            (min..=max)
+                .contains(&drift)
+                .then(|| current[state][index(drift)])
+        })
+        .fold(0, |total, count| (total + count) % modulus)
+}
+
+/// Reads springs one at a time, keeping track of which segment it is in and how many broken
+/// springs of that segment it has seen so far.
+struct Automaton<'a> {
+    segments: &'a [u32],
+    /// The first state of every segment, where it hasn't started yet.
+    offsets: Vec<usize>,
+}
+
+impl<'a> Automaton<'a> {
+    fn new(segments: &'a [u32]) -> Self {
+        let offsets = segments
+            .iter()
+            .scan(0, |offset, &length| {
+                let start = *offset;
+                *offset += length as usize + 1;
+                Some(start)
+            })
+            .collect();
+
+        Automaton { segments, offsets }
+    }
+
+    fn len(&self) -> usize {
+        self.offsets.last().unwrap() + *self.segments.last().unwrap() as usize + 1
+    }
+
+    fn decode(&self, state: usize) -> (usize, u32) {
+        let segment = self.offsets.partition_point(|&offset| offset <= state) - 1;
+        (segment, (state - self.offsets[segment]) as u32)
+    }
+
+    /// The next state, and whether a segment was just finished.
+    fn step(&self, state: usize, spring: Spring) -> Option<(usize, bool)> {
+        let (segment, broken) = self.decode(state);
+        match spring {
+            Spring::Broken if broken < self.segments[segment] => Some((state + 1, false)),
+            Spring::Operational if broken == 0 => Some((state, false)),
+            Spring::Operational if broken == self.segments[segment] => {
+                let next = (segment + 1) % self.segments.len();
+                Some((self.offsets[next], true))
+            }
+            _ => None,
+        }
+    }
+
+    /// The number of ways to read `springs` from `start`, by end state and drift.
+    fn run(&self, start: usize, springs: &[Spring], modulus: u64) -> HashMap<(usize, i64), u64> {
+        let mut current = HashMap::from([((start, 0), 1 % modulus)]);
+
+        for &spring in springs {
+            let mut next = HashMap::new();
+            for ((state, finished), count) in current {
+                let options = match spring {
+                    Spring::Unknown => vec![Spring::Operational, Spring::Broken],
+                    spring => vec![spring],
+                };
+                for (state, done) in options.into_iter().filter_map(|s| self.step(state, s)) {
+                    let entry = next.entry((state, finished + done as i64)).or_insert(0);
+                    *entry = (*entry + count) % modulus;
+                }
+            }
+            current = next;
+        }
+
+        let expected = self.segments.len() as i64;
+        current
+            .into_iter()
+            .map(|((state, finished), count)| ((state, finished - expected), count))
+            .collect()
+    }
+
+    /// The drift a state must have at the end of the line to be a valid arrangement.
+    fn accepting_drift(&self, state: usize) -> Option<i64> {
+        match self.decode(state) {
+            (0, 0) => Some(0),
+            (segment, broken)
+                if segment == self.segments.len() - 1 && broken == self.segments[segment] =>
+            {
+                // Finishing this segment brings the drift back to zero.
+                Some(-1)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The states that are on the way from the start to a valid arrangement.
+fn useful_states(
+    automaton: &Automaton,
+    initial: &HashMap<(usize, i64), u64>,
+    edges: &Edges,
+) -> Vec<bool> {
+    let states = edges.len();
+
+    let mut reachable = vec![false; states];
+    let mut queue: VecDeque<_> = initial.keys().map(|&(state, _)| state).collect();
+    while let Some(state) = queue.pop_front() {
+        if !std::mem::replace(&mut reachable[state], true) {
+            queue.extend(edges[state].keys().map(|&(to, _)| to));
+        }
+    }
+
+    let mut useful = vec![false; states];
+    let mut queue: VecDeque<_> = (0..states)
+        .filter(|&state| automaton.accepting_drift(state).is_some())
+        .collect();
+    while let Some(state) = queue.pop_front() {
+        if !std::mem::replace(&mut useful[state], true) {
+            queue
+                .extend((0..states).filter(|&from| edges[from].keys().any(|&(to, _)| to == state)));
+        }
+    }
+
+    (0..states)
+        .map(|state| reachable[state] && useful[state])
+        .collect()
+}
+
+/// Assigns every useful state a connected component and a drift relative to that component, such
+/// that every transition changes the drift by exactly the difference. Returns `None` if that is
+/// impossible, meaning the drift is unbounded.
+fn potential(useful: &[bool], edges: &Edges) -> Option<Vec<Option<(usize, i64)>>> {
+    let states = edges.len();
+    let mut neighbours = vec![Vec::new(); states];
+    for (from, targets) in edges.iter().enumerate().filter(|(s, _)| useful[*s]) {
+        for &(to, drift) in targets.keys().filter(|&&(t, _)| useful[t]) {
+            neighbours[from].push((to, drift));
+            neighbours[to].push((from, -drift));
+        }
+    }
+
+    let mut potential: Vec<Option<(usize, i64)>> = vec![None; states];
+    for root in (0..states).filter(|&state| useful[state]) {
+        if potential[root].is_some() {
+            continue;
+        }
+        potential[root] = Some((root, 0));
+        let mut queue = VecDeque::from([root]);
+        while let Some(state) = queue.pop_front() {
+            let (_, phi) = potential[state].unwrap();
+            for &(next, drift) in &neighbours[state] {
+                match potential[next] {
+                    None => {
+                        potential[next] = Some((root, phi + drift));
+                        queue.push_back(next);
+                    }
+                    Some((_, existing)) if existing != phi + drift => return None,
+                    Some(_) => (),
+                }
+            }
+        }
+    }
+
+    Some(potential)
+}
+
+fn matrix_pow(mut base: Vec<Vec<u64>>, mut exponent: u64, modulus: u64) -> Vec<Vec<u64>> {
+    let size = base.len();
+    let mut result: Vec<Vec<u64>> = (0..size)
+        .map(|row| (0..size).map(|col| (row == col) as u64 % modulus).collect())
+        .collect();
+
+    while exponent > 0 {
+        if exponent % 2 == 1 {
+            result = matrix_mul(&result, &base, modulus);
+        }
+        base = matrix_mul(&base, &base, modulus);
+        exponent /= 2;
+    }
+
+    result
+}
+
+fn matrix_mul(a: &[Vec<u64>], b: &[Vec<u64>], modulus: u64) -> Vec<Vec<u64>> {
+    let size = a.len();
+    let mut product = vec![vec![0; size]; size];
+    for row in 0..size {
+        for middle in (0..size).filter(|&middle| a[row][middle] != 0) {
+            for col in 0..size {
+                product[row][col] = (product[row][col]
+                    + mul_mod(a[row][middle], b[middle][col], modulus))
+                    % modulus;
+            }
+        }
+    }
+    product
+}
+
+fn mul_mod(a: u64, b: u64, modulus: u64) -> u64 {
+    (a as u128 * b as u128 % modulus as u128) as u64
+}
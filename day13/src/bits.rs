@@ -0,0 +1,65 @@
+use std::{iter, slice};
+
+const WORD: usize = u128::BITS as usize;
+
+/// A row or column of a pattern, with a set bit for every rock.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Bits {
+    /// Lines of up to 128 cells, compared in one go.
+    Short(u128),
+    /// Longer lines, split into words of 128 cells.
+    Long(Vec<u128>),
+}
+
+impl Bits {
+    pub fn new(rocks: impl ExactSizeIterator<Item = bool>) -> Self {
+        let mut words = vec![0; rocks.len().div_ceil(WORD).max(1)];
+        for (index, rock) in rocks.enumerate() {
+            words[index / WORD] |= (rock as u128) << (index % WORD);
+        }
+
+        if words.len() == 1 {
+            Bits::Short(words[0])
+        } else {
+            Bits::Long(words)
+        }
+    }
+
+    pub fn get(&self, index: usize) -> bool {
+        self.words()[index / WORD] >> (index % WORD) & 1 == 1
+    }
+
+    /// The number of cells that differ.
+    pub fn distance(&self, other: &Bits) -> u32 {
+        self.words()
+            .iter()
+            .zip(other.words())
+            .map(|(a, b)| (a ^ b).count_ones())
+            .sum()
+    }
+
+    /// The indices of the cells that differ.
+    pub fn differences<'a>(&'a self, other: &'a Bits) -> impl Iterator<Item = usize> + 'a {
+        self.words()
+            .iter()
+            .zip(other.words())
+            .enumerate()
+            .flat_map(|(word, (a, b))| {
+                let mut different = a ^ b;
+                iter::from_fn(move || {
+                    (different != 0).then(|| {
+                        let bit = different.trailing_zeros() as usize;
+                        different &= different - 1;
+                        word * WORD + bit
+                    })
+                })
+            })
+    }
+
+    fn words(&self) -> &[u128] {
+        match self {
+            Bits::Short(word) => slice::from_ref(word),
+            Bits::Long(words) => words,
+        }
+    }
+}
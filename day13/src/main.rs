@@ -1,8 +1,69 @@
-use std::str::FromStr;
+mod bits;
+mod symmetry;
+
+use std::{
+    env,
+    fmt::{self, Display, Formatter},
+    ops::Range,
+    str::FromStr,
+};
+
+use crate::{
+    bits::Bits,
+    symmetry::{Extent, Symmetry},
+};
 
 fn main() {
     let input = include_str!("../../inputs/input13");
 
+    if env::args().nth(1).as_deref() == Some("reflections") {
+        let index = env::args().nth(2).unwrap().parse().unwrap();
+        let pattern = Pattern::from_str(input.split("\n\n").nth(index).unwrap()).unwrap();
+        for reflection in pattern.reflections() {
+            println!("{reflection}");
+        }
+        return;
+    }
+
+    if env::args().nth(1).as_deref() == Some("symmetries") {
+        let index = env::args().nth(2).unwrap().parse().unwrap();
+        let pattern = Pattern::from_str(input.split("\n\n").nth(index).unwrap()).unwrap();
+        match env::args().nth(3) {
+            Some(smudges) => match pattern.symmetry(smudges.parse().unwrap()) {
+                Some(symmetry) => println!("{symmetry}"),
+                None => println!("No symmetry"),
+            },
+            None => pattern
+                .symmetries()
+                .for_each(|symmetry| println!("{symmetry}")),
+        }
+        return;
+    }
+
+    if env::args().nth(1).as_deref() == Some("smudges") {
+        let smudges = env::args().nth(2).unwrap().parse().unwrap();
+        let mut total = 0;
+        let mut missing = Vec::new();
+        for (index, reflection) in reflections_with(input, smudges).enumerate() {
+            match reflection {
+                Some(reflection) => {
+                    println!("{index}: {reflection}");
+                    total += reflection.summary();
+                }
+                None => {
+                    println!("{index}: no reflection with {smudges} smudges");
+                    missing.push(index);
+                }
+            }
+        }
+        if missing.is_empty() {
+            println!("Total: {total}");
+        } else {
+            println!("Total without patterns {missing:?}: {total}");
+        }
+        return;
+    }
+
     println!("Part One: {}", solve_13a(input));
     println!("Part Two: {}", solve_13b(input));
 }
@@ -11,7 +72,7 @@ fn solve_13a(input: &str) -> u32 {
     input
         .split("\n\n")
         .map(|pattern| Pattern::from_str(pattern).unwrap())
-        .map(|pattern| pattern.summarize())
+        .map(|pattern| pattern.summarize(0))
         .sum()
 }
 
@@ -19,109 +80,188 @@ fn solve_13b(input: &str) -> u32 {
     input
         .split("\n\n")
         .map(|pattern| Pattern::from_str(pattern).unwrap())
-        .map(|pattern| pattern.summarize_smudged())
+        .map(|pattern| pattern.summarize(1))
         .sum()
 }
 
+/// The first reflection with exactly `smudges` mismatched pairs in every pattern, if it has one.
+fn reflections_with(input: &str, smudges: usize) -> impl Iterator<Item = Option<Reflection>> + '_ {
+    input
+        .split("\n\n")
+        .map(move |pattern| Pattern::from_str(pattern).unwrap().reflection(smudges))
+}
+
+/// Every row and column of a pattern as a bitmask, so lines can be compared in one go.
 struct Pattern {
-    tiles: Vec<Vec<Tile>>,
-    width: usize,
-    height: usize,
+    rows: Vec<Bits>,
+    columns: Vec<Bits>,
 }
 
 impl Pattern {
-    fn summarize(&self) -> u32 {
-        match self.find_horizontal_mirror() {
-            None => self.find_vertical_mirror().expect("no solution") as u32,
-            Some(value) => value as u32 * 100,
-        }
+    fn summarize(&self, smudges: usize) -> u32 {
+        self.reflection(smudges).expect("no solution").summary()
     }
 
-    fn find_horizontal_mirror(&self) -> Option<usize> {
-        for row in 0..self.height - 1 {
-            let next_row = row + 1;
-
-            if (0..=row.min(self.height - next_row - 1))
-                .all(|offset| self.tiles[row - offset] == self.tiles[next_row + offset])
-            {
-                return Some(next_row);
-            }
-        }
+    /// The first reflection, horizontal ones first, with exactly `smudges` mismatched pairs.
+    fn reflection(&self, smudges: usize) -> Option<Reflection> {
+        [Axis::Horizontal, Axis::Vertical]
+            .into_iter()
+            .flat_map(|axis| (1..self.lines(axis).len()).map(move |position| (axis, position)))
+            .find(|&(axis, position)| self.distance(axis, position) == smudges as u32)
+            .map(|(axis, position)| self.reflect(axis, position))
+    }
 
-        None
+    /// Every line between two rows or columns, with the cells that don't match their mirror image.
+    fn reflections(&self) -> impl Iterator<Item = Reflection> + '_ {
+        [Axis::Horizontal, Axis::Vertical]
+            .into_iter()
+            .flat_map(move |axis| {
+                (1..self.lines(axis).len()).map(move |position| self.reflect(axis, position))
+            })
     }
 
-    fn find_vertical_mirror(&self) -> Option<usize> {
-        for col in 0..self.width - 1 {
-            let next_col = col + 1;
+    fn reflect(&self, axis: Axis, position: usize) -> Reflection {
+        let reach = position.min(self.lines(axis).len() - position);
 
-            if (0..=col.min(self.width - next_col - 1)).all(|offset| {
-                self.tiles
-                    .iter()
-                    .all(|row| row[col - offset] == row[next_col + offset])
-            }) {
-                return Some(next_col);
-            }
+        Reflection {
+            symmetry: Symmetry::Between(axis, position),
+            extent: self.line_extent(axis, position - reach..position + reach),
+            smudges: self.line_smudges(axis, self.mirrored(axis, position)),
         }
+    }
 
-        None
+    /// The cells that differ between every pair of rows or columns.
+    fn line_smudges(
+        &self,
+        axis: Axis,
+        pairs: impl Iterator<Item = (usize, usize)>,
+    ) -> Vec<(Pos, Pos)> {
+        let lines = self.lines(axis);
+        pairs
+            .flat_map(|(before, after)| {
+                lines[before]
+                    .differences(&lines[after])
+                    .map(move |index| (axis.cell(before, index), axis.cell(after, index)))
+            })
+            .collect()
     }
 
-    fn summarize_smudged(&self) -> u32 {
-        match self.find_horizontal_mirror_smudged() {
-            None => self.find_vertical_mirror_smudged().expect("no solution") as u32,
-            Some(value) => value as u32 * 100,
+    /// The extent covering a range of whole rows or columns.
+    fn line_extent(&self, axis: Axis, lines: Range<usize>) -> Extent {
+        match axis {
+            Axis::Horizontal => Extent {
+                top: lines.start,
+                left: 0,
+                height: lines.len(),
+                width: self.columns.len(),
+            },
+            Axis::Vertical => Extent {
+                top: 0,
+                left: lines.start,
+                height: self.rows.len(),
+                width: lines.len(),
+            },
         }
     }
 
-    fn find_horizontal_mirror_smudged(&self) -> Option<usize> {
-        let mut smudge_cleaned = false;
+    /// The number of cells that don't match their mirror image.
+    fn distance(&self, axis: Axis, position: usize) -> u32 {
+        let lines = self.lines(axis);
+        self.mirrored(axis, position)
+            .map(|(before, after)| lines[before].distance(&lines[after]))
+            .sum()
+    }
 
-        for row in 0..self.height - 1 {
-            let next_row = row + 1;
+    /// The indices of every pair of rows or columns that the mirror maps onto each other.
+    fn mirrored(&self, axis: Axis, position: usize) -> impl Iterator<Item = (usize, usize)> {
+        let lines = self.lines(axis).len();
+        (0..position.min(lines - position))
+            .map(move |offset| (position - 1 - offset, position + offset))
+    }
 
-            if (0..=row.min(self.height - next_row - 1)).all(|offset| {
-                self.tiles[row - offset]
-                    .iter()
-                    .zip(&self.tiles[next_row + offset])
-                    .all(|(top, bottom)| {
-                        *top == *bottom
-                            // Ignore the first pair of tiles that is not equal
-                            || (!smudge_cleaned).then(|| smudge_cleaned = true).is_some()
-                    })
-            }) && smudge_cleaned
-            {
-                return Some(next_row);
-            }
-            smudge_cleaned = false;
+    /// The rows for horizontal mirrors, or columns for vertical ones.
+    fn lines(&self, axis: Axis) -> &[Bits] {
+        match axis {
+            Axis::Horizontal => &self.rows,
+            Axis::Vertical => &self.columns,
         }
+    }
 
-        None
+    fn get(&self, pos: Pos) -> bool {
+        self.rows[pos.row].get(pos.col)
     }
+}
 
-    fn find_vertical_mirror_smudged(&self) -> Option<usize> {
-        let mut smudge_cleaned = false;
+#[derive(Debug, Eq, PartialEq)]
+struct Reflection {
+    symmetry: Symmetry,
+    extent: Extent,
+    /// Every pair of cells that differ from each other's image. Fixing either cell of a pair
+    /// removes the mismatch.
+    smudges: Vec<(Pos, Pos)>,
+}
 
-        for col in 0..self.width - 1 {
-            let next_col = col + 1;
+impl Reflection {
+    fn summary(&self) -> u32 {
+        match self.symmetry {
+            Symmetry::Between(Axis::Horizontal, position) => position as u32 * 100,
+            Symmetry::Between(Axis::Vertical, position) => position as u32,
+            _ => panic!("not a reflection between rows or columns"),
+        }
+    }
+}
 
-            if (0..=col.min(self.width - next_col - 1)).all(|offset| {
-                self.tiles.iter().all(|row| {
-                    row[col - offset] == row[next_col + offset]
-                        // Ignore the first pair of tiles that is not equal
-                        || (!smudge_cleaned).then(|| smudge_cleaned = true).is_some()
-                })
-            }) && smudge_cleaned
-            {
-                return Some(next_col);
-            }
-            smudge_cleaned = false;
+impl Display for Reflection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Extent {
+            top,
+            left,
+            height,
+            width,
+        } = self.extent;
+        write!(
+            f,
+            "{:?} over {height}x{width} at ({top},{left}), {} smudges",
+            self.symmetry,
+            self.smudges.len()
+        )?;
+        for (a, b) in &self.smudges {
+            write!(f, " ({},{})/({},{})", a.row, a.col, b.row, b.col)?;
         }
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Axis {
+    /// A mirror between two rows.
+    Horizontal,
+    /// A mirror between two columns.
+    Vertical,
+}
 
-        None
+impl Axis {
+    /// The position of the cell at `index` along row or column `line`.
+    fn cell(self, line: usize, index: usize) -> Pos {
+        match self {
+            Axis::Horizontal => Pos {
+                row: line,
+                col: index,
+            },
+            Axis::Vertical => Pos {
+                row: index,
+                col: line,
+            },
+        }
     }
 }
 
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+struct Pos {
+    row: usize,
+    col: usize,
+}
+
 impl FromStr for Pattern {
     type Err = ();
 
@@ -130,16 +270,20 @@ impl FromStr for Pattern {
             .lines()
             .map(|line| line.chars().map(Tile::from).collect())
             .collect();
-        let width = tiles.first().unwrap().len();
-        let height = tiles.len();
+        let width = tiles.first().ok_or(())?.len();
+        if tiles.iter().any(|row| row.len() != width) {
+            return Err(());
+        }
 
-        let pattern = Pattern {
-            tiles,
-            width,
-            height,
-        };
+        let rows = tiles
+            .iter()
+            .map(|row| Bits::new(row.iter().map(|&tile| tile == Tile::Rock)))
+            .collect();
+        let columns = (0..width)
+            .map(|col| Bits::new(tiles.iter().map(|row| row[col] == Tile::Rock)))
+            .collect();
 
-        Ok(pattern)
+        Ok(Pattern { rows, columns })
     }
 }
 
@@ -161,7 +305,13 @@ impl From<char> for Tile {
 
 #[cfg(test)]
 mod tests {
-    use crate::{solve_13a, solve_13b};
+    use std::str::FromStr;
+
+    use crate::{
+        reflections_with, solve_13a, solve_13b,
+        symmetry::{Extent, Symmetry},
+        Axis, Pattern, Pos,
+    };
 
     const INPUT: &str = "#.##..##.
 ..#.##.#.
@@ -189,4 +339,110 @@ mod tests {
     fn test_13b() {
         assert_eq!(solve_13b(INPUT), 400)
     }
+
+    #[test]
+    fn test_smudges() {
+        let pattern = Pattern::from_str(INPUT.split("\n\n").next().unwrap()).unwrap();
+
+        let clean = pattern.reflection(0).unwrap();
+        assert_eq!(clean.symmetry, Symmetry::Between(Axis::Vertical, 5));
+
+        let smudged = pattern.reflection(1).unwrap();
+        assert_eq!(smudged.symmetry, Symmetry::Between(Axis::Horizontal, 3));
+        assert_eq!(
+            smudged.smudges,
+            vec![(Pos { row: 0, col: 0 }, Pos { row: 5, col: 0 })]
+        );
+
+        // Every line between rows or columns is reported, however many cells differ.
+        assert_eq!(pattern.reflections().count(), 6 + 8);
+        assert!(pattern.reflection(2).is_some());
+
+        // The last pattern only has lines with two mismatched pairs.
+        let input = format!("{INPUT}\n#.\n.#\n");
+        let found: Vec<_> = reflections_with(&input, 1).map(|r| r.is_some()).collect();
+        assert_eq!(found, [true, true, false]);
+        assert!(reflections_with(&input, 2).nth(2).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_wide_pattern() {
+        // 300 columns, mirrored down the middle, with a smudge beyond the first 128 columns.
+        let half: Vec<String> = (0..3)
+            .map(|row| {
+                (0..150)
+                    .map(|col| {
+                        if (col * 7 + row * 3) % 5 < 2 {
+                            '#'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        let mut rows: Vec<String> = half
+            .iter()
+            .map(|half| half.clone() + &half.chars().rev().collect::<String>())
+            .collect();
+        let pattern = Pattern::from_str(&rows.join("\n")).unwrap();
+        assert_eq!(pattern.summarize(0), 150);
+
+        let flipped = if &rows[1][200..201] == "#" { "." } else { "#" };
+        rows[1].replace_range(200..201, flipped);
+        let pattern = Pattern::from_str(&rows.join("\n")).unwrap();
+        let smudged = pattern.reflection(1).unwrap();
+        assert_eq!(smudged.symmetry, Symmetry::Between(Axis::Vertical, 150));
+        assert_eq!(
+            smudged.smudges,
+            vec![(Pos { row: 1, col: 99 }, Pos { row: 1, col: 200 })]
+        );
+    }
+
+    #[test]
+    fn test_symmetries() {
+        let exact = |pattern: &str| {
+            let pattern = Pattern::from_str(pattern).unwrap();
+            pattern
+                .symmetries()
+                .filter(|symmetry| symmetry.smudges.is_empty())
+                .map(|symmetry| (symmetry.symmetry, symmetry.extent))
+                .collect::<Vec<_>>()
+        };
+        let square = |top, left, size| Extent {
+            top,
+            left,
+            height: size,
+            width: size,
+        };
+
+        assert_eq!(
+            exact(".#.\n##.\n.#."),
+            vec![(Symmetry::Through(Axis::Horizontal, 1), square(0, 0, 3))]
+        );
+        assert_eq!(
+            exact("##.\n.#.\n.##"),
+            vec![(Symmetry::Rotation, square(0, 0, 3))]
+        );
+        assert_eq!(
+            exact("#..\n.#.\n#.#\n..#"),
+            vec![(Symmetry::AntiDiagonal, square(0, 0, 3))]
+        );
+        // Squares in the middle of the long side count too, not just those at its ends.
+        assert_eq!(
+            exact("##.\n#.#\n...\n#..\n.##"),
+            vec![(Symmetry::Diagonal, square(1, 0, 3))]
+        );
+
+        // The lower square is one smudge away from being symmetric along its diagonal.
+        let pattern = Pattern::from_str("#..\n.#.\n#.#\n..#").unwrap();
+        let smudged = pattern
+            .symmetries()
+            .find(|symmetry| symmetry.symmetry == Symmetry::Diagonal && symmetry.extent.top == 1)
+            .unwrap();
+        assert_eq!(
+            smudged.smudges,
+            vec![(Pos { row: 2, col: 2 }, Pos { row: 3, col: 1 })]
+        );
+    }
 }
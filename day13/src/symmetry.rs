@@ -0,0 +1,120 @@
+use crate::{Axis, Pattern, Pos, Reflection};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Symmetry {
+    /// A mirror between two rows or columns, with `position` of them before it.
+    Between(Axis, usize),
+    /// A mirror through the middle of the row or column at `position`.
+    Through(Axis, usize),
+    /// Half a turn around the centre of the pattern.
+    Rotation,
+    /// A mirror along the diagonal from the top left to the bottom right of a square.
+    Diagonal,
+    /// A mirror along the diagonal from the top right to the bottom left of a square.
+    AntiDiagonal,
+}
+
+/// The part of a pattern that a symmetry maps onto itself.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Extent {
+    pub top: usize,
+    pub left: usize,
+    pub height: usize,
+    pub width: usize,
+}
+
+impl Pattern {
+    /// Every symmetry of any kind, with the cells that don't match their image.
+    pub fn symmetries(&self) -> impl Iterator<Item = Reflection> + '_ {
+        let whole = Extent {
+            top: 0,
+            left: 0,
+            height: self.rows.len(),
+            width: self.columns.len(),
+        };
+
+        // Diagonals only exist on squares, so slide the largest square along the longer side.
+        let size = whole.height.min(whole.width);
+        let squares = (0..=whole.height - size)
+            .flat_map(move |top| (0..=whole.width - size).map(move |left| (top, left)));
+
+        self.reflections()
+            .chain(self.through_reflections())
+            .chain([self.check(Symmetry::Rotation, whole)])
+            .chain(squares.flat_map(move |(top, left)| {
+                let square = Extent {
+                    top,
+                    left,
+                    height: size,
+                    width: size,
+                };
+                [
+                    self.check(Symmetry::Diagonal, square),
+                    self.check(Symmetry::AntiDiagonal, square),
+                ]
+            }))
+    }
+
+    /// The first symmetry with exactly `smudges` mismatched pairs.
+    pub fn symmetry(&self, smudges: usize) -> Option<Reflection> {
+        self.symmetries()
+            .find(|symmetry| symmetry.smudges.len() == smudges)
+    }
+
+    fn through_reflections(&self) -> impl Iterator<Item = Reflection> + '_ {
+        [Axis::Horizontal, Axis::Vertical]
+            .into_iter()
+            .flat_map(move |axis| {
+                let lines = self.lines(axis).len();
+                (1..lines.saturating_sub(1)).map(move |position| {
+                    let reach = position.min(lines - 1 - position);
+                    let pairs = (1..=reach).map(|offset| (position - offset, position + offset));
+                    Reflection {
+                        symmetry: Symmetry::Through(axis, position),
+                        extent: self.line_extent(axis, position - reach..position + reach + 1),
+                        smudges: self.line_smudges(axis, pairs),
+                    }
+                })
+            })
+    }
+
+    /// Compares every cell in the extent with its image under a rotation or diagonal mirror.
+    fn check(&self, symmetry: Symmetry, extent: Extent) -> Reflection {
+        let Extent {
+            top,
+            left,
+            height,
+            width,
+        } = extent;
+
+        let smudges = (0..height)
+            .flat_map(|row| (0..width).map(move |col| (row, col)))
+            .filter_map(|(row, col)| {
+                let (image_row, image_col) = match symmetry {
+                    Symmetry::Rotation => (height - 1 - row, width - 1 - col),
+                    Symmetry::Diagonal => (col, row),
+                    Symmetry::AntiDiagonal => (width - 1 - col, height - 1 - row),
+                    Symmetry::Between(..) | Symmetry::Through(..) => unreachable!(),
+                };
+                // Every pair is visited twice, so only keep it from its first cell.
+                ((row, col) < (image_row, image_col)).then_some((
+                    Pos {
+                        row: top + row,
+                        col: left + col,
+                    },
+                    Pos {
+                        row: top + image_row,
+                        col: left + image_col,
+                    },
+                ))
+            })
+            .filter(|&(a, b)| self.get(a) != self.get(b))
+            .collect();
+
+        Reflection {
+            symmetry,
+            extent,
+            smudges,
+        }
+    }
+}
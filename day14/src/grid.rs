@@ -0,0 +1,169 @@
+use std::{
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+use crate::Tile;
+
+/// The original platform, with a tile for every cell. The bitboard is checked against it.
+pub struct Grid {
+    tiles: Vec<Vec<Tile>>,
+    width: usize,
+    height: usize,
+}
+
+impl Grid {
+    pub fn north_load(&self) -> u32 {
+        self.tiles
+            .iter()
+            .rev()
+            .enumerate()
+            .map(|(index, row)| (1 + index as u32, row))
+            .map(|(multiplier, row)| {
+                row.iter().filter(|tile| **tile == Tile::Round).count() as u32 * multiplier
+            })
+            .sum()
+    }
+
+    pub fn tilt_up(&mut self) {
+        for col in 0..self.width {
+            let mut start_row = 0;
+
+            while start_row < self.height {
+                let mut row = start_row;
+                let mut round_rocks = 0;
+
+                while row < self.height && self.tiles[row][col] != Tile::Square {
+                    if self.tiles[row][col] == Tile::Round {
+                        round_rocks += 1;
+                        self.tiles[row][col] = Tile::Space;
+                    }
+
+                    row += 1;
+                }
+
+                for row in start_row..start_row + round_rocks {
+                    self.tiles[row][col] = Tile::Round;
+                }
+
+                start_row = row + 1;
+            }
+        }
+    }
+
+    pub fn tilt_left(&mut self) {
+        for row in 0..self.height {
+            let mut start_col = 0;
+
+            while start_col < self.width {
+                let mut col = start_col;
+                let mut round_rocks = 0;
+
+                while col < self.width && self.tiles[row][col] != Tile::Square {
+                    if self.tiles[row][col] == Tile::Round {
+                        round_rocks += 1;
+                        self.tiles[row][col] = Tile::Space;
+                    }
+
+                    col += 1;
+                }
+
+                for col in start_col..start_col + round_rocks {
+                    self.tiles[row][col] = Tile::Round;
+                }
+
+                start_col = col + 1;
+            }
+        }
+    }
+
+    pub fn tilt_down(&mut self) {
+        for col in 0..self.width as isize {
+            let mut start_row = self.height as isize - 1;
+
+            while start_row >= 0 {
+                let mut row = start_row;
+                let mut round_rocks = 0;
+
+                while row >= 0 && self.tiles[row as usize][col as usize] != Tile::Square {
+                    if self.tiles[row as usize][col as usize] == Tile::Round {
+                        round_rocks += 1;
+                        self.tiles[row as usize][col as usize] = Tile::Space;
+                    }
+
+                    row -= 1;
+                }
+
+                for row in start_row - round_rocks + 1..=start_row {
+                    self.tiles[row as usize][col as usize] = Tile::Round;
+                }
+
+                start_row = row - 1;
+            }
+        }
+    }
+
+    pub fn tilt_right(&mut self) {
+        for row in 0..self.height as isize {
+            let mut start_col = self.width as isize - 1;
+
+            while start_col >= 0 {
+                let mut col = start_col;
+                let mut round_rocks = 0;
+
+                while col >= 0 && self.tiles[row as usize][col as usize] != Tile::Square {
+                    if self.tiles[row as usize][col as usize] == Tile::Round {
+                        round_rocks += 1;
+                        self.tiles[row as usize][col as usize] = Tile::Space;
+                    }
+
+                    col -= 1;
+                }
+
+                for col in start_col - round_rocks + 1..=start_col {
+                    self.tiles[row as usize][col as usize] = Tile::Round;
+                }
+
+                start_col = col - 1;
+            }
+        }
+    }
+}
+
+impl Display for Grid {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for row in &self.tiles {
+            for tile in row {
+                match tile {
+                    Tile::Space => write!(f, ".")?,
+                    Tile::Round => write!(f, "O")?,
+                    Tile::Square => write!(f, "#")?,
+                }
+            }
+
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Grid {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tiles: Vec<Vec<_>> = s
+            .lines()
+            .map(|line| line.chars().map(Tile::from_char).collect())
+            .collect();
+
+        let width = tiles[0].len();
+        let height = tiles.len();
+
+        Ok(Grid {
+            tiles,
+            width,
+            height,
+        })
+    }
+}
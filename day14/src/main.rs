@@ -1,182 +1,218 @@
+#[cfg(test)]
+mod grid;
+mod program;
+
 use std::{
-    collections::HashMap,
+    env,
     fmt::{Display, Formatter},
+    iter,
     str::FromStr,
 };
 
+use crate::program::Program;
+
 fn main() {
     let input = include_str!("../../inputs/input14");
 
+    if env::args().nth(1).as_deref() == Some("run") {
+        let program = Program::from_str(&env::args().nth(2).unwrap()).unwrap();
+        let mut platform = Platform::from_str(input).unwrap();
+        program.run(&mut platform);
+        for edge in Direction::ALL {
+            println!("{edge:?} load: {}", platform.load(edge));
+        }
+        return;
+    }
+
     println!("Part One: {}", solve_14a(input));
     println!("Part Two: {}", solve_14b(input));
 }
 
 fn solve_14a(input: &str) -> u32 {
-    let mut platform = Platform::from_str(input).unwrap();
-    platform.tilt_up();
-    platform.north_load()
+    run(input, "N", Direction::North)
 }
 
 fn solve_14b(input: &str) -> u32 {
-    let mut platform = Platform::from_str(input).unwrap();
-    let mut seen = HashMap::new();
-    seen.insert(platform.tiles.clone(), 0);
-    for cycles in 1..=1000000000 {
-        platform.tilt_up();
-        platform.tilt_left();
-        platform.tilt_down();
-        platform.tilt_right();
-
-        if let Some(previous_cycle) = seen.get(&platform.tiles) {
-            let repeating_cycle = cycles - *previous_cycle;
-            let remaining_cycles = 1000000000 - cycles;
-            let remaining_cycles = remaining_cycles % repeating_cycle;
-            for _ in 0..remaining_cycles {
-                platform.tilt_up();
-                platform.tilt_left();
-                platform.tilt_down();
-                platform.tilt_right();
-            }
-            break;
-        }
+    run(input, "(NWSE)x1000000000", Direction::North)
+}
 
-        seen.insert(platform.tiles.clone(), cycles);
-    }
-    platform.north_load()
+/// Runs a program on the platform, and measures the load on one of its edges.
+fn run(input: &str, program: &str, edge: Direction) -> u32 {
+    let mut platform = Platform::from_str(input).unwrap();
+    Program::from_str(program).unwrap().run(&mut platform);
+    platform.load(edge)
 }
 
+const WORD: usize = u128::BITS as usize;
+
+/// The rocks on a platform, as one bit per cell. Every row takes as many words as its width
+/// needs, so that a whole row of rocks can be moved at once.
+#[derive(Clone, Eq, PartialEq, Hash)]
 struct Platform {
-    tiles: Vec<Vec<Tile>>,
+    round: Vec<u128>,
+    square: Vec<u128>,
     width: usize,
-    height: usize,
 }
 
 impl Platform {
-    fn north_load(&self) -> u32 {
-        self.tiles
-            .iter()
-            .rev()
-            .enumerate()
-            .map(|(index, row)| (1 + index as u32, row))
-            .map(|(multiplier, row)| {
-                row.iter().filter(|tile| **tile == Tile::Round).count() as u32 * multiplier
-            })
-            .sum()
+    /// The total load of the round rocks on the given edge, where each rock weighs as much as
+    /// the number of rows or columns from the opposite edge up to and including its own.
+    fn load(&self, edge: Direction) -> u32 {
+        let height = self.height();
+        let mut load = 0;
+        for (row, rocks) in self.round.chunks(self.words()).enumerate() {
+            let count: u32 = rocks.iter().map(|word| word.count_ones()).sum();
+            load += match edge {
+                Direction::North => (height - row) as u32 * count,
+                Direction::South => (row + 1) as u32 * count,
+                Direction::West => bits(rocks).map(|col| (self.width - col) as u32).sum(),
+                Direction::East => bits(rocks).map(|col| (col + 1) as u32).sum(),
+            };
+        }
+        load
     }
 
-    fn tilt_up(&mut self) {
-        for col in 0..self.width {
-            let mut start_row = 0;
-
-            while start_row < self.height {
-                let mut row = start_row;
-                let mut round_rocks = 0;
+    fn tilt(&mut self, direction: Direction) {
+        match direction {
+            Direction::North => self.tilt_vertically((0..self.height()).collect()),
+            Direction::South => self.tilt_vertically((0..self.height()).rev().collect()),
+            Direction::West => self.tilt_horizontally(false),
+            Direction::East => self.tilt_horizontally(true),
+        }
+    }
 
-                while row < self.height && self.tiles[row][col] != Tile::Square {
-                    if self.tiles[row][col] == Tile::Round {
-                        round_rocks += 1;
-                        self.tiles[row][col] = Tile::Space;
+    /// Moves all rocks towards the first row in `order`. Each row of rocks rolls through the rows
+    /// before it, which have already settled, leaving behind the rocks that are blocked.
+    fn tilt_vertically(&mut self, order: Vec<usize>) {
+        let words = self.words();
+        for word in 0..words {
+            for (index, &row) in order.iter().enumerate() {
+                let mut rolling = std::mem::take(&mut self.round[row * words + word]);
+                let mut current = row;
+                for &next in order[..index].iter().rev() {
+                    let free =
+                        !(self.round[next * words + word] | self.square[next * words + word]);
+                    self.round[current * words + word] |= rolling & !free;
+                    rolling &= free;
+                    current = next;
+                    if rolling == 0 {
+                        break;
                     }
-
-                    row += 1;
-                }
-
-                for row in start_row..start_row + round_rocks {
-                    self.tiles[row][col] = Tile::Round;
                 }
-
-                start_row = row + 1;
+                self.round[current * words + word] |= rolling;
             }
         }
     }
 
-    fn tilt_left(&mut self) {
-        for row in 0..self.height {
-            let mut start_col = 0;
-
-            while start_col < self.width {
-                let mut col = start_col;
-                let mut round_rocks = 0;
-
-                while col < self.width && self.tiles[row][col] != Tile::Square {
-                    if self.tiles[row][col] == Tile::Round {
-                        round_rocks += 1;
-                        self.tiles[row][col] = Tile::Space;
+    /// Moves all rocks in every row one column at a time, until none of them can move. Rocks
+    /// move to higher columns if `east`, and to lower ones otherwise.
+    fn tilt_horizontally(&mut self, east: bool) {
+        let words = self.words();
+        let mask = self.mask();
+
+        // Rows of up to 128 cells, like the puzzle's, are shifted in one go.
+        if words == 1 {
+            let forward = |rocks: u128| if east { rocks << 1 } else { rocks >> 1 };
+            let backward = |rocks: u128| if east { rocks >> 1 } else { rocks << 1 };
+            for (round, &square) in self.round.iter_mut().zip(&self.square) {
+                loop {
+                    let free = !(*round | square) & mask[0];
+                    let moving = *round & backward(free);
+                    if moving == 0 {
+                        break;
                     }
-
-                    col += 1;
+                    *round = *round & !moving | forward(moving);
                 }
-
-                for col in start_col..start_col + round_rocks {
-                    self.tiles[row][col] = Tile::Round;
-                }
-
-                start_col = col + 1;
             }
+            return;
         }
-    }
-
-    fn tilt_down(&mut self) {
-        for col in 0..self.width as isize {
-            let mut start_row = self.height as isize - 1;
-
-            while start_row >= 0 {
-                let mut row = start_row;
-                let mut round_rocks = 0;
-
-                while row >= 0 && self.tiles[row as usize][col as usize] != Tile::Square {
-                    if self.tiles[row as usize][col as usize] == Tile::Round {
-                        round_rocks += 1;
-                        self.tiles[row as usize][col as usize] = Tile::Space;
-                    }
 
-                    row -= 1;
+        let mut free = vec![0; words];
+        let mut moving = vec![0; words];
+        let mut shifted = vec![0; words];
+        for (round, square) in self.round.chunks_mut(words).zip(self.square.chunks(words)) {
+            loop {
+                for (((free, round), square), mask) in
+                    free.iter_mut().zip(&*round).zip(square).zip(&mask)
+                {
+                    *free = !(round | square) & mask;
                 }
-
-                for row in start_row - round_rocks + 1..=start_row {
-                    self.tiles[row as usize][col as usize] = Tile::Round;
+                // A rock can move if the cell it would move into is free.
+                shift(&free, !east, &mut shifted);
+                for ((moving, round), shifted) in moving.iter_mut().zip(&*round).zip(&shifted) {
+                    *moving = round & shifted;
+                }
+                if moving.iter().all(|&word| word == 0) {
+                    break;
+                }
+                shift(&moving, east, &mut shifted);
+                for ((round, moving), shifted) in round.iter_mut().zip(&moving).zip(&shifted) {
+                    *round = *round & !moving | shifted;
                 }
-
-                start_row = row - 1;
             }
         }
     }
 
-    fn tilt_right(&mut self) {
-        for row in 0..self.height as isize {
-            let mut start_col = self.width as isize - 1;
-
-            while start_col >= 0 {
-                let mut col = start_col;
-                let mut round_rocks = 0;
-
-                while col >= 0 && self.tiles[row as usize][col as usize] != Tile::Square {
-                    if self.tiles[row as usize][col as usize] == Tile::Round {
-                        round_rocks += 1;
-                        self.tiles[row as usize][col as usize] = Tile::Space;
-                    }
+    /// The bits of a row that are on the platform.
+    fn mask(&self) -> Vec<u128> {
+        (0..self.words())
+            .map(|word| {
+                let cells = (self.width - word * WORD).min(WORD);
+                u128::MAX >> (WORD - cells)
+            })
+            .collect()
+    }
 
-                    col -= 1;
-                }
+    fn words(&self) -> usize {
+        self.width.div_ceil(WORD)
+    }
 
-                for col in start_col - round_rocks + 1..=start_col {
-                    self.tiles[row as usize][col as usize] = Tile::Round;
-                }
+    fn height(&self) -> usize {
+        self.round.len() / self.words()
+    }
+}
 
-                start_col = col - 1;
-            }
-        }
+/// Shifts a row one column to the east (higher columns) or west, carrying bits between words.
+fn shift(row: &[u128], east: bool, shifted: &mut [u128]) {
+    for (index, shifted) in shifted.iter_mut().enumerate() {
+        *shifted = if east {
+            let carry = index
+                .checked_sub(1)
+                .map_or(0, |previous| row[previous] >> (WORD - 1));
+            row[index] << 1 | carry
+        } else {
+            let carry = row.get(index + 1).map_or(0, |next| next << (WORD - 1));
+            row[index] >> 1 | carry
+        };
     }
 }
 
+/// The indices of the set bits in a row.
+fn bits(row: &[u128]) -> impl Iterator<Item = usize> + '_ {
+    row.iter().enumerate().flat_map(|(index, &word)| {
+        let mut word = word;
+        iter::from_fn(move || {
+            (word != 0).then(|| {
+                let bit = word.trailing_zeros() as usize;
+                word &= word - 1;
+                index * WORD + bit
+            })
+        })
+    })
+}
+
 impl Display for Platform {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        for row in &self.tiles {
-            for tile in row {
-                match tile {
-                    Tile::Space => write!(f, ".")?,
-                    Tile::Round => write!(f, "O")?,
-                    Tile::Square => write!(f, "#")?,
+        let words = self.words();
+        for (round, square) in self.round.chunks(words).zip(self.square.chunks(words)) {
+            for col in 0..self.width {
+                let (word, bit) = (col / WORD, col % WORD);
+                if round[word] >> bit & 1 == 1 {
+                    write!(f, "O")?;
+                } else if square[word] >> bit & 1 == 1 {
+                    write!(f, "#")?;
+                } else {
+                    write!(f, ".")?;
                 }
             }
 
@@ -187,27 +223,44 @@ impl Display for Platform {
     }
 }
 
+/// Fails for empty platforms, or with rows of different widths.
 impl FromStr for Platform {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let tiles: Vec<Vec<_>> = s
-            .lines()
-            .map(|line| line.chars().map(Tile::from_char).collect())
-            .collect();
+        let width = s.lines().next().ok_or(())?.len();
+        if width == 0 {
+            return Err(());
+        }
 
-        let width = tiles[0].len();
-        let height = tiles.len();
+        let words = width.div_ceil(WORD);
+        let mut round = Vec::new();
+        let mut square = Vec::new();
+        for line in s.lines() {
+            if line.len() != width {
+                return Err(());
+            }
+            let (mut round_row, mut square_row) = (vec![0; words], vec![0; words]);
+            for (col, c) in line.chars().enumerate() {
+                match Tile::from_char(c) {
+                    Tile::Space => (),
+                    Tile::Round => round_row[col / WORD] |= 1 << (col % WORD),
+                    Tile::Square => square_row[col / WORD] |= 1 << (col % WORD),
+                }
+            }
+            round.extend(round_row);
+            square.extend(square_row);
+        }
 
         Ok(Platform {
-            tiles,
+            round,
+            square,
             width,
-            height,
         })
     }
 }
 
-#[derive(Clone, Eq, PartialEq, Hash)]
+#[derive(Copy, Clone, Eq, PartialEq)]
 enum Tile {
     Space,
     Round,
@@ -225,9 +278,42 @@ impl Tile {
     }
 }
 
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Direction {
+    North,
+    West,
+    South,
+    East,
+}
+
+impl Direction {
+    const ALL: [Direction; 4] = [
+        Direction::North,
+        Direction::West,
+        Direction::South,
+        Direction::East,
+    ];
+
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            'N' => Some(Self::North),
+            'W' => Some(Self::West),
+            'S' => Some(Self::South),
+            'E' => Some(Self::East),
+            _ => None,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::{solve_14a, solve_14b};
+    use std::str::FromStr;
+
+    use crate::{
+        grid::Grid,
+        program::{Instruction, Program, ProgramError},
+        run, solve_14a, solve_14b, Direction, Platform,
+    };
 
     const INPUT: &str = "O....#....
 O.OO#....#
@@ -250,4 +336,90 @@ O.#..O.#.#
     fn test_14b() {
         assert_eq!(solve_14b(INPUT), 64)
     }
+
+    #[test]
+    fn test_program() {
+        assert_eq!(run(INPUT, "(NWSE)x1000000000", Direction::North), 64);
+        assert_eq!(
+            run(INPUT, "(NWSE)x3", Direction::East),
+            run(INPUT, "NWSENWSENWSE", Direction::East)
+        );
+        assert_eq!(run(INPUT, "Nx2 W", Direction::North), 136);
+
+        // Tilting south is tilting north upside down.
+        let flipped: Vec<_> = INPUT.lines().rev().collect();
+        assert_eq!(
+            run(INPUT, "S", Direction::South),
+            run(&flipped.join("\n"), "N", Direction::North)
+        );
+
+        assert_eq!(
+            Program::from_str("(NW)x2 E"),
+            Ok(Program(vec![
+                Instruction::Repeat(
+                    vec![
+                        Instruction::Tilt(Direction::North),
+                        Instruction::Tilt(Direction::West)
+                    ],
+                    2
+                ),
+                Instruction::Tilt(Direction::East),
+            ]))
+        );
+        assert_eq!(Program::from_str("(NW"), Err(ProgramError::UnclosedGroup));
+        assert_eq!(
+            Program::from_str("NQ"),
+            Err(ProgramError::UnexpectedChar(1, 'Q'))
+        );
+        assert_eq!(Program::from_str("Nx"), Err(ProgramError::MissingCount(1)));
+    }
+
+    #[test]
+    fn test_bitboard() {
+        // Pseudo-random platforms that fill one word per row exactly, and that spill into a
+        // second word.
+        let mut seed = 0x2545f4914f6cdd1d_u64;
+        let mut random = move || {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed
+        };
+        let mut platform = |width| {
+            let rows: Vec<String> = (0..40)
+                .map(|_| {
+                    (0..width)
+                        .map(|_| match random() % 5 {
+                            0 => '#',
+                            1 | 2 => 'O',
+                            _ => '.',
+                        })
+                        .collect()
+                })
+                .collect();
+            rows.join("\n")
+        };
+        let (full, wide) = (platform(128), platform(200));
+
+        for input in [INPUT, &full, &wide] {
+            let mut platform = Platform::from_str(input).unwrap();
+            let mut grid = Grid::from_str(input).unwrap();
+            assert_eq!(platform.to_string(), grid.to_string());
+
+            for _ in 0..50 {
+                let direction = Direction::ALL[random() as usize % 4];
+                platform.tilt(direction);
+                match direction {
+                    Direction::North => grid.tilt_up(),
+                    Direction::West => grid.tilt_left(),
+                    Direction::South => grid.tilt_down(),
+                    Direction::East => grid.tilt_right(),
+                }
+                assert_eq!(platform.to_string(), grid.to_string());
+                assert_eq!(platform.load(Direction::North), grid.north_load());
+            }
+        }
+
+        assert!(Platform::from_str("O.\nO").is_err());
+    }
 }
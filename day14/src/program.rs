@@ -0,0 +1,111 @@
+use std::{iter::Peekable, str::CharIndices, str::FromStr};
+
+use crate::{Direction, Platform};
+
+/// Repeats below this are run directly, as finding the cycle would take longer.
+const SKIP_THRESHOLD: usize = 1000;
+
+/// A sequence of tilts, such as `(NWSE)x1000000000 N`. Any tilt or parenthesised group can be
+/// followed by `x` and a number to repeat it.
+#[derive(Debug, Eq, PartialEq)]
+pub struct Program(pub Vec<Instruction>);
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum Instruction {
+    Tilt(Direction),
+    Repeat(Vec<Instruction>, usize),
+}
+
+impl Program {
+    pub fn run(&self, platform: &mut Platform) {
+        run(&self.0, platform);
+    }
+}
+
+fn run(instructions: &[Instruction], platform: &mut Platform) {
+    for instruction in instructions {
+        match instruction {
+            Instruction::Tilt(direction) => platform.tilt(*direction),
+            Instruction::Repeat(body, count) if *count < SKIP_THRESHOLD => {
+                for _ in 0..*count {
+                    run(body, platform);
+                }
+            }
+            Instruction::Repeat(body, count) => {
+                let step = |platform: &Platform| {
+                    let mut platform = platform.clone();
+                    run(body, &mut platform);
+                    platform
+                };
+                let cycle = cycle::fingerprint(platform, step);
+                *platform = cycle.nth(platform.clone(), step, *count);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum ProgramError {
+    UnexpectedChar(usize, char),
+    UnclosedGroup,
+    MissingCount(usize),
+}
+
+impl FromStr for Program {
+    type Err = ProgramError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.char_indices().peekable();
+        let instructions = parse_sequence(&mut chars)?;
+        match chars.next() {
+            None => Ok(Program(instructions)),
+            Some((index, c)) => Err(ProgramError::UnexpectedChar(index, c)),
+        }
+    }
+}
+
+/// Parses instructions up to the end of the input or a closing parenthesis.
+fn parse_sequence(chars: &mut Peekable<CharIndices>) -> Result<Vec<Instruction>, ProgramError> {
+    let mut instructions = Vec::new();
+
+    while let Some(&(index, c)) = chars.peek() {
+        let item = match c {
+            ')' => break,
+            c if c.is_whitespace() => {
+                chars.next();
+                continue;
+            }
+            '(' => {
+                chars.next();
+                let body = parse_sequence(chars)?;
+                if chars.next().is_none() {
+                    return Err(ProgramError::UnclosedGroup);
+                }
+                body
+            }
+            c => {
+                chars.next();
+                let direction =
+                    Direction::from_char(c).ok_or(ProgramError::UnexpectedChar(index, c))?;
+                vec![Instruction::Tilt(direction)]
+            }
+        };
+
+        if let Some(&(index, 'x')) = chars.peek() {
+            chars.next();
+            let mut digits = String::new();
+            while let Some(&(_, digit)) = chars.peek().filter(|(_, c)| c.is_ascii_digit()) {
+                digits.push(digit);
+                chars.next();
+            }
+            let count = digits
+                .parse()
+                .map_err(|_| ProgramError::MissingCount(index))?;
+            instructions.push(Instruction::Repeat(item, count));
+        } else {
+            instructions.extend(item);
+        }
+    }
+
+    Ok(instructions)
+}
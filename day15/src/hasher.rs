@@ -0,0 +1,34 @@
+use std::hash::{BuildHasher, Hasher};
+
+/// The Holiday ASCII String Helper algorithm, over any bytes.
+///
+/// Hashing a `str` through `Hash` also writes a terminating `0xff` byte, so to get the value
+/// from the puzzle, pass the bytes to `write` directly.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct HolidayHasher {
+    state: u8,
+}
+
+impl Hasher for HolidayHasher {
+    fn finish(&self) -> u64 {
+        self.state as u64
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.state = self.state.wrapping_add(byte).wrapping_mul(17);
+        }
+    }
+}
+
+/// Lets `std` collections use HASH, with only 256 different hashes.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct BuildHolidayHasher;
+
+impl BuildHasher for BuildHolidayHasher {
+    type Hasher = HolidayHasher;
+
+    fn build_hasher(&self) -> Self::Hasher {
+        HolidayHasher::default()
+    }
+}
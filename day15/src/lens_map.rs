@@ -0,0 +1,113 @@
+use std::{
+    hash::{BuildHasher, Hash},
+    mem,
+};
+
+/// A hash table that keeps the entries of every bucket in insertion order, like the boxes of
+/// lenses in the puzzle. Entries that are replaced keep their place, removed entries lose it.
+pub struct LensMap<K, V, S> {
+    buckets: Vec<Vec<(K, V)>>,
+    hash_builder: S,
+    len: usize,
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> LensMap<K, V, S> {
+    pub fn with_hasher(buckets: usize, hash_builder: S) -> Self {
+        assert!(buckets > 0, "a map needs at least one bucket");
+
+        LensMap {
+            buckets: (0..buckets).map(|_| Vec::new()).collect(),
+            hash_builder,
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.buckets[self.bucket(key)]
+            .iter()
+            .find_map(|(k, v)| (k == key).then_some(v))
+    }
+
+    /// Replaces the value of an existing key in place, or adds it to the end of its bucket.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let bucket = self.bucket(&key);
+        match self.buckets[bucket].iter_mut().find(|(k, _)| *k == key) {
+            Some((_, old)) => Some(mem::replace(old, value)),
+            None => {
+                self.buckets[bucket].push((key, value));
+                self.len += 1;
+                None
+            }
+        }
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let bucket = self.bucket(key);
+        let index = self.buckets[bucket].iter().position(|(k, _)| k == key)?;
+        self.len -= 1;
+        Some(self.buckets[bucket].remove(index).1)
+    }
+
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V> {
+        let bucket = self.bucket(&key);
+        let index = self.buckets[bucket].iter().position(|(k, _)| *k == key);
+
+        Entry {
+            bucket: &mut self.buckets[bucket],
+            len: &mut self.len,
+            key,
+            index,
+        }
+    }
+
+    /// Every entry, by bucket and then by insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.buckets.iter().flatten().map(|(k, v)| (k, v))
+    }
+
+    /// The sum of the weights of all values, each multiplied by the one-based number of its bucket
+    /// and its one-based position within that bucket.
+    pub fn weighted_sum(&self, weight: impl Fn(&V) -> u64) -> u64 {
+        self.buckets
+            .iter()
+            .enumerate()
+            .flat_map(|(bucket, entries)| {
+                entries
+                    .iter()
+                    .enumerate()
+                    .map(move |(slot, (_, value))| (bucket, slot, value))
+            })
+            .map(|(bucket, slot, value)| (bucket as u64 + 1) * (slot as u64 + 1) * weight(value))
+            .sum()
+    }
+
+    fn bucket(&self, key: &K) -> usize {
+        (self.hash_builder.hash_one(key) % self.buckets.len() as u64) as usize
+    }
+}
+
+pub struct Entry<'a, K, V> {
+    bucket: &'a mut Vec<(K, V)>,
+    len: &'a mut usize,
+    key: K,
+    /// The position of the key in its bucket, if it is already there.
+    index: Option<usize>,
+}
+
+impl<'a, K, V> Entry<'a, K, V> {
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        let index = match self.index {
+            Some(index) => index,
+            None => {
+                self.bucket.push((self.key, default));
+                *self.len += 1;
+                self.bucket.len() - 1
+            }
+        };
+        &mut self.bucket[index].1
+    }
+}
@@ -1,36 +1,119 @@
-use std::str::FromStr;
+mod hasher;
+mod lens_map;
+
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashSet},
+    env,
+    fmt::{Display, Formatter},
+    fs,
+    hash::{BuildHasher, Hash, Hasher},
+    str::FromStr,
+};
+
+use crate::{hasher::BuildHolidayHasher, lens_map::LensMap};
 
 fn main() {
     let input = include_str!("../../inputs/input15");
 
-    println!("Part One: {}", solve_14a(input));
-    println!("Part Two: {}", solve_14b(input));
+    match env::args().nth(1).as_deref() {
+        Some("lookup") => {
+            let label = env::args().nth(2).unwrap();
+            match boxes(input).get(&Label(label.clone())) {
+                Some(focal_length) => println!("{label}: {focal_length}"),
+                None => println!("{label} is not in any box"),
+            }
+        }
+        Some("labels") => {
+            // How often every label is used, in the order of their boxes.
+            let mut uses = LensMap::with_hasher(256, BuildHolidayHasher);
+            for op in input.trim().split(',') {
+                let label = op.split(['=', '-']).next().unwrap();
+                *uses.entry(Label(label.to_owned())).or_insert(0) += 1;
+            }
+            println!("{} labels", uses.len());
+            for (label, count) in uses.iter() {
+                println!("{label}: {count}");
+            }
+        }
+        Some("collisions") => {
+            let input = match env::args().nth(2) {
+                Some(path) => fs::read_to_string(path).unwrap(),
+                None => input.to_owned(),
+            };
+            let boxes = labels_by_box(&input);
+
+            let labels: usize = boxes.iter().map(Vec::len).sum();
+            let used = boxes.iter().filter(|labels| !labels.is_empty()).count();
+            println!("{labels} labels in {used} of {} boxes", boxes.len());
+
+            let mut sizes = BTreeMap::new();
+            for labels in &boxes {
+                *sizes.entry(labels.len()).or_insert(0) += 1;
+            }
+            for (size, count) in sizes {
+                println!("{count} boxes with {size} labels");
+            }
+
+            let mut crowded: Vec<_> = boxes.iter().enumerate().collect();
+            crowded.sort_by_key(|(_, labels)| Reverse(labels.len()));
+            for (index, labels) in crowded.into_iter().take(5) {
+                println!("Box {index}: {}", labels.join(", "));
+            }
+        }
+        _ => {
+            println!("Part One: {}", solve_14a(input));
+            println!("Part Two: {}", solve_14b(input));
+        }
+    }
 }
 
 fn solve_14a(input: &str) -> u32 {
     input.trim().split(',').map(hash).sum()
 }
 
-fn solve_14b(input: &str) -> u32 {
+fn solve_14b(input: &str) -> u64 {
+    boxes(input).weighted_sum(|&focal_length| focal_length as u64)
+}
+
+/// The lenses in every box after running all operations.
+fn boxes(input: &str) -> LensMap<Label, u32, BuildHolidayHasher> {
     let ops = input
         .trim()
         .split(',')
         .map(|op| Operation::from_str(op).unwrap());
-    let mut map = HashMap::new();
+    let mut map = LensMap::with_hasher(256, BuildHolidayHasher);
 
     for op in ops {
         match op {
             Operation::Insert(key, value) => map.insert(key, value),
             Operation::Remove(key) => map.remove(&key),
-        }
+        };
     }
 
-    map.focusing_power()
+    map
+}
+
+/// The label of a lens. It hashes as just its bytes, so that `BuildHolidayHasher` puts it in the
+/// box from the puzzle.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Label(String);
+
+impl Hash for Label {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write(self.0.as_bytes());
+    }
+}
+
+impl Display for Label {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
 }
 
 enum Operation {
-    Insert(String, u32),
-    Remove(String),
+    Insert(Label, u32),
+    Remove(Label),
 }
 
 impl FromStr for Operation {
@@ -40,75 +123,50 @@ impl FromStr for Operation {
         match s.split_once('=') {
             None => {
                 let key = s.strip_suffix('-').unwrap();
-                Ok(Operation::Remove(key.to_owned()))
+                Ok(Operation::Remove(Label(key.to_owned())))
             }
             Some((key, value)) => {
                 let value = value.parse().unwrap();
-                Ok(Operation::Insert(key.to_owned(), value))
+                Ok(Operation::Insert(Label(key.to_owned()), value))
             }
         }
     }
 }
 
 fn hash(input: &str) -> u32 {
-    input
-        .chars()
-        .fold(0, |acc, c| ((acc + c as u32) * 17) % 256)
+    let mut hasher = BuildHolidayHasher.build_hasher();
+    hasher.write(input.as_bytes());
+    hasher.finish() as u32
 }
 
-struct HashMap {
-    boxes: [Vec<(String, u32)>; 256],
-}
+/// Every distinct label in the operations, grouped by the box it ends up in, in order of first
+/// use.
+fn labels_by_box(input: &str) -> Vec<Vec<&str>> {
+    let mut seen = HashSet::with_hasher(BuildHolidayHasher);
+    let mut boxes = vec![Vec::new(); 256];
 
-impl HashMap {
-    fn new() -> Self {
-        Self {
-            boxes: [(); 256].map(|_| Vec::new()),
+    for op in input.trim().split(',') {
+        let label = op.split(['=', '-']).next().unwrap();
+        if seen.insert(label) {
+            boxes[hash(label) as usize].push(label);
         }
     }
 
-    fn focusing_power(&self) -> u32 {
-        self.boxes
-            .iter()
-            .enumerate()
-            .filter(|(_, current_box)| !current_box.is_empty())
-            .map(|(index, current_box)| {
-                (index + 1) as u32
-                    * current_box
-                        .iter()
-                        .enumerate()
-                        .map(|(index, (_, value))| (index + 1) as u32 * *value)
-                        .sum::<u32>()
-            })
-            .sum()
-    }
-
-    fn insert(&mut self, key: String, value: u32) {
-        let hash = hash(&key);
-        let current_box = &mut self.boxes[hash as usize];
-
-        match current_box
-            .iter_mut()
-            .find_map(|(k, v)| (*k == key).then_some(v))
-        {
-            None => current_box.push((key, value)),
-            Some(old_value) => *old_value = value,
-        }
-    }
-
-    fn remove(&mut self, key: &str) {
-        let hash = hash(key);
-        let current_box = &mut self.boxes[hash as usize];
-
-        if let Some(index) = current_box.iter().position(|(k, _)| *k == key) {
-            current_box.remove(index);
-        }
-    }
+    boxes
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{solve_14a, solve_14b};
+    use std::{
+        collections::{hash_map::RandomState, HashMap},
+        hash::BuildHasher,
+    };
+
+    use proptest::prelude::*;
+
+    use crate::{
+        hash, hasher::BuildHolidayHasher, labels_by_box, lens_map::LensMap, solve_14a, solve_14b,
+    };
 
     const INPUT: &str = "rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7";
 
@@ -121,4 +179,81 @@ mod tests {
     fn test_14b() {
         assert_eq!(solve_14b(INPUT), 145)
     }
+
+    #[test]
+    fn test_hasher() {
+        assert_eq!(hash("HASH"), 52);
+        assert_eq!(hash("rn"), 0);
+        assert_eq!(hash("qp"), 1);
+
+        // Works with std collections, even though everything ends up in one of 256 hashes.
+        let mut map = HashMap::with_hasher(BuildHolidayHasher);
+        for (index, op) in INPUT.split(',').enumerate() {
+            map.insert(op, index);
+        }
+        assert_eq!(map.len(), 11);
+        assert_eq!(map["pc-"], 8);
+    }
+
+    #[test]
+    fn test_collisions() {
+        let boxes = labels_by_box(INPUT);
+
+        assert_eq!(boxes[0], ["rn", "cm"]);
+        assert_eq!(boxes[1], ["qp"]);
+        assert_eq!(boxes[3], ["pc", "ot", "ab"]);
+        assert_eq!(boxes.iter().map(Vec::len).sum::<usize>(), 6);
+    }
+
+    #[derive(Clone, Debug)]
+    enum Op {
+        Insert(u8, u32),
+        Remove(u8),
+    }
+
+    fn op() -> impl Strategy<Value = Op> {
+        prop_oneof![
+            (any::<u8>(), any::<u32>()).prop_map(|(key, value)| Op::Insert(key, value)),
+            any::<u8>().prop_map(Op::Remove),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn test_lens_map_matches_std(
+            ops in prop::collection::vec(op(), 0..200),
+            buckets in 1..20_usize,
+        ) {
+            let hash_builder = RandomState::new();
+            let mut map = LensMap::with_hasher(buckets, hash_builder.clone());
+            let mut expected = HashMap::new();
+            // Every key in the order it was last added, as replacing a value keeps its place.
+            let mut order = Vec::new();
+
+            for op in ops {
+                match op {
+                    Op::Insert(key, value) => {
+                        prop_assert_eq!(map.insert(key, value), expected.insert(key, value));
+                        if !order.contains(&key) {
+                            order.push(key);
+                        }
+                    }
+                    Op::Remove(key) => {
+                        prop_assert_eq!(map.remove(&key), expected.remove(&key));
+                        order.retain(|&k| k != key);
+                    }
+                }
+                prop_assert_eq!(map.len(), expected.len());
+            }
+
+            for key in 0..=u8::MAX {
+                prop_assert_eq!(map.get(&key), expected.get(&key));
+            }
+
+            let mut by_bucket = order.clone();
+            by_bucket.sort_by_key(|key| hash_builder.hash_one(key) % buckets as u64);
+            let keys: Vec<_> = map.iter().map(|(&key, _)| key).collect();
+            prop_assert_eq!(keys, by_bucket);
+        }
+    }
 }
@@ -1,10 +1,26 @@
-use std::{collections::BTreeSet, str::FromStr};
+use std::{
+    collections::BTreeSet,
+    env,
+    fmt::{Display, Write},
+    str::FromStr,
+};
+
+use num_bigint::BigUint;
 
 fn main() {
     let input = include_str!("../../inputs/input4");
 
-    println!("Part One: {}", solve_4a(input));
-    println!("Part Two: {}", solve_4b(input));
+    match env::args().nth(1).as_deref() {
+        Some("explain") => print!("{}", explain(input)),
+        Some("big") => {
+            println!("Part One: {}", solve_4a(input));
+            println!("Part Two: {}", solve_4b_big(input));
+        }
+        _ => {
+            println!("Part One: {}", solve_4a(input));
+            println!("Part Two: {}", solve_4b(input));
+        }
+    }
 }
 
 fn solve_4a(input: &str) -> u32 {
@@ -15,23 +31,156 @@ fn solve_4a(input: &str) -> u32 {
         .sum()
 }
 
-fn solve_4b(input: &str) -> u32 {
-    let mut multipliers: Vec<_> = input.lines().map(|_| 1).collect();
+fn solve_4b(input: &str) -> u64 {
+    let cards = parse(input);
+    cascade::<u64>(&cards)
+        .and_then(|copies| total(&copies))
+        .expect("card copies overflow u64, run with `big` instead")
+}
+
+fn solve_4b_big(input: &str) -> BigUint {
+    let cards = parse(input);
+    // Additions on big integers never overflow, so both of these always succeed.
+    total(&cascade::<BigUint>(&cards).unwrap()).unwrap()
+}
+
+/// The explanation as CSV, counted with big integers only if the copies don't fit in a `u64`.
+fn explain(input: &str) -> String {
+    let cards = parse(input);
+    match Explanation::<u64>::new(&cards) {
+        Some(explanation) => explanation.to_csv(),
+        // Additions on big integers never overflow, so this always succeeds.
+        None => Explanation::<BigUint>::new(&cards).unwrap().to_csv(),
+    }
+}
 
-    for (index, card) in input
+fn parse(input: &str) -> Vec<Card> {
+    input
         .lines()
         .map(|line| Card::from_str(line).unwrap())
-        .enumerate()
-    {
-        for i in 0..card.common() {
-            let this_multiplier = multipliers[index];
-            if let Some(multiplier) = multipliers.get_mut(index + 1 + i as usize) {
-                *multiplier += this_multiplier;
-            };
+        .collect()
+}
+
+/// Computes how many copies of each card end up being scratched, or `None` if any count does not
+/// fit in `C`.
+fn cascade<C: Count>(cards: &[Card]) -> Option<Vec<C>> {
+    let mut copies = vec![C::one(); cards.len()];
+
+    for (index, card) in cards.iter().enumerate() {
+        let this_copies = copies[index].clone();
+        for copy in copies
+            .iter_mut()
+            .skip(index + 1)
+            .take(card.common() as usize)
+        {
+            *copy = copy.checked_add(&this_copies)?;
         }
     }
 
-    multipliers.into_iter().sum()
+    Some(copies)
+}
+
+fn total<C: Count>(copies: &[C]) -> Option<C> {
+    copies
+        .iter()
+        .try_fold(C::zero(), |acc, copy| acc.checked_add(copy))
+}
+
+pub trait Count: Clone + Display {
+    fn zero() -> Self;
+
+    fn one() -> Self;
+
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+}
+
+impl Count for u64 {
+    fn zero() -> Self {
+        0
+    }
+
+    fn one() -> Self {
+        1
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        u64::checked_add(*self, *other)
+    }
+}
+
+impl Count for BigUint {
+    fn zero() -> Self {
+        BigUint::ZERO
+    }
+
+    fn one() -> Self {
+        BigUint::from(1_u8)
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        Some(self + other)
+    }
+}
+
+/// For every card, the earlier cards its copies were won from.
+pub struct Explanation<C> {
+    cards: Vec<CardCopies<C>>,
+}
+
+impl<C: Count> Explanation<C> {
+    pub fn new(cards: &[Card]) -> Option<Self> {
+        let mut explained: Vec<CardCopies<C>> = cards
+            .iter()
+            .map(|_| CardCopies {
+                sources: Vec::new(),
+            })
+            .collect();
+
+        for (index, card) in cards.iter().enumerate() {
+            let this_copies = explained[index].copies()?;
+            for target in explained
+                .iter_mut()
+                .skip(index + 1)
+                .take(card.common() as usize)
+            {
+                target.sources.push((index + 1, this_copies.clone()));
+            }
+        }
+
+        // Make sure the last cards fit as well, not only the ones that won something.
+        explained
+            .iter()
+            .try_for_each(|card| card.copies().map(|_| ()))?;
+
+        Some(Explanation { cards: explained })
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("card,source,copies\n");
+
+        for (index, card) in self.cards.iter().enumerate() {
+            writeln!(csv, "{},original,1", index + 1).unwrap();
+            for (source, copies) in &card.sources {
+                writeln!(csv, "{},{},{}", index + 1, source, copies).unwrap();
+            }
+        }
+
+        csv
+    }
+}
+
+pub struct CardCopies<C> {
+    /// Pairs of (card number, copies won from that card).
+    sources: Vec<(usize, C)>,
+}
+
+impl<C: Count> CardCopies<C> {
+    /// The original card plus all copies won from earlier cards.
+    pub fn copies(&self) -> Option<C> {
+        self.sources
+            .iter()
+            .try_fold(C::one(), |acc, (_, copies)| acc.checked_add(copies))
+    }
 }
 
 pub struct Card {
@@ -87,3 +236,55 @@ impl FromStr for Numbers {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use num_bigint::BigUint;
+
+    use crate::{cascade, explain, parse, solve_4a, solve_4b, solve_4b_big, total};
+
+    const INPUT: &str = "Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
+Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
+Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
+Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
+Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
+Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11
+";
+
+    #[test]
+    fn test_4a() {
+        assert_eq!(solve_4a(INPUT), 13)
+    }
+
+    #[test]
+    fn test_4b() {
+        assert_eq!(solve_4b(INPUT), 30);
+        assert_eq!(solve_4b_big(INPUT), BigUint::from(30_u8));
+    }
+
+    #[test]
+    fn test_overflow() {
+        // Every card wins a copy of each of the next ten cards, so the counts nearly double from
+        // one card to the next.
+        let input: String = (1..=70)
+            .map(|n| format!("Card {n}: 1 2 3 4 5 6 7 8 9 10 | 1 2 3 4 5 6 7 8 9 10\n"))
+            .collect();
+        let cards = parse(&input);
+
+        assert!(cascade::<u64>(&cards).is_none());
+        assert!(total(&cascade::<BigUint>(&cards).unwrap()).unwrap() > BigUint::from(u64::MAX));
+
+        // Explaining falls back to big integers as well.
+        let csv = explain(&input);
+        let last = csv.lines().last().unwrap().rsplit(',').next().unwrap();
+        assert!(last.parse::<BigUint>().unwrap() > BigUint::from(u64::MAX));
+    }
+
+    #[test]
+    fn test_explain() {
+        let csv = explain(INPUT);
+        let card_4: Vec<_> = csv.lines().filter(|line| line.starts_with("4,")).collect();
+
+        assert_eq!(card_4, ["4,original,1", "4,1,1", "4,2,2", "4,3,4"]);
+    }
+}
@@ -0,0 +1,133 @@
+use std::{collections::HashMap, str::FromStr};
+
+use crate::piecewise::{IntervalSet, Piecewise, PiecewiseError};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum AlmanacError {
+    /// Two sections convert from the same category.
+    DuplicateSource(String),
+    /// Following the maps from some category eventually leads back to it.
+    Cycle(Vec<String>),
+    /// There is no map from the category reached so far towards the requested one.
+    MissingLink { from: String, to: String },
+    /// A section does not start where the previous section ended.
+    OutOfOrder { source: String, expected: String },
+    /// A line of a map is not a valid range.
+    InvalidRange(PiecewiseError),
+}
+
+pub struct Map {
+    source: String,
+    destination: String,
+    function: Piecewise,
+}
+
+impl FromStr for Map {
+    type Err = PiecewiseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (header, s) = s.split_once(" map:\n").unwrap();
+        let (source, destination) = header.split_once("-to-").unwrap();
+
+        Ok(Map {
+            source: source.to_owned(),
+            destination: destination.to_owned(),
+            function: s.parse()?,
+        })
+    }
+}
+
+/// The maps of an almanac, forming a graph between categories.
+pub struct Almanac {
+    maps: Vec<Map>,
+    by_source: HashMap<String, usize>,
+}
+
+impl Almanac {
+    pub fn new(maps: Vec<Map>) -> Result<Self, AlmanacError> {
+        let mut by_source = HashMap::new();
+        for (index, map) in maps.iter().enumerate() {
+            if by_source.insert(map.source.clone(), index).is_some() {
+                return Err(AlmanacError::DuplicateSource(map.source.clone()));
+            }
+        }
+
+        let almanac = Almanac { maps, by_source };
+
+        for map in &almanac.maps {
+            let mut path = vec![map.source.as_str()];
+            let mut current = map.source.as_str();
+            while let Some(next) = almanac.next(current) {
+                current = &next.destination;
+                if let Some(start) = path.iter().position(|&category| category == current) {
+                    let mut cycle: Vec<_> = path[start..].iter().map(|&c| c.to_owned()).collect();
+                    cycle.push(current.to_owned());
+                    return Err(AlmanacError::Cycle(cycle));
+                }
+                path.push(current);
+            }
+        }
+
+        Ok(almanac)
+    }
+
+    /// The maps to apply, in order, to convert from one category to another.
+    pub fn path(&self, from: &str, to: &str) -> Result<Vec<&Map>, AlmanacError> {
+        let mut path = Vec::new();
+        let mut current = from;
+
+        while current != to {
+            let map = self
+                .next(current)
+                .ok_or_else(|| AlmanacError::MissingLink {
+                    from: current.to_owned(),
+                    to: to.to_owned(),
+                })?;
+            path.push(map);
+            current = &map.destination;
+        }
+
+        Ok(path)
+    }
+
+    /// All maps between two categories composed into a single function.
+    pub fn function(&self, from: &str, to: &str) -> Result<Piecewise, AlmanacError> {
+        Ok(self
+            .path(from, to)?
+            .into_iter()
+            .fold(Piecewise::identity(), |function, map| {
+                function.then(&map.function)
+            }))
+    }
+
+    pub fn convert(&self, from: &str, to: &str, value: u64) -> Result<u64, AlmanacError> {
+        Ok(self.function(from, to)?.get(value))
+    }
+
+    pub fn convert_ranges(
+        &self,
+        from: &str,
+        to: &str,
+        values: &IntervalSet,
+    ) -> Result<IntervalSet, AlmanacError> {
+        Ok(self.function(from, to)?.image(values))
+    }
+
+    /// Checks that every section continues from the category the previous one converted to.
+    pub fn check_order(&self) -> Result<(), AlmanacError> {
+        for (previous, map) in self.maps.iter().zip(self.maps.iter().skip(1)) {
+            if map.source != previous.destination {
+                return Err(AlmanacError::OutOfOrder {
+                    source: map.source.clone(),
+                    expected: previous.destination.clone(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn next(&self, source: &str) -> Option<&Map> {
+        self.by_source.get(source).map(|&index| &self.maps[index])
+    }
+}
@@ -1,73 +1,94 @@
 extern crate core;
 
-use std::str::FromStr;
+mod almanac;
+mod piecewise;
+
+use std::{env, iter, str::FromStr};
 
 use itertools::Itertools;
 
+use crate::{
+    almanac::{Almanac, AlmanacError, Map},
+    piecewise::IntervalSet,
+};
+
 fn main() {
     let input = include_str!("../../inputs/input5");
 
-    println!("Part One: {}", solve_5a(input));
-    println!("Part Two: {}", solve_5b(input));
+    match env::args().nth(1).as_deref() {
+        Some("convert") => {
+            let mut args = env::args().skip(2);
+            let (from, to) = (args.next().unwrap(), args.next().unwrap());
+            let value = args.next().unwrap().parse().unwrap();
+            let input = Input::from_str(input).unwrap();
+            println!("{}", input.almanac.convert(&from, &to, value).unwrap());
+        }
+        Some("seeds") => {
+            let location = env::args().nth(2).unwrap().parse().unwrap();
+            for seeds in find_seeds(input, location).iter() {
+                println!("{seeds:?}");
+            }
+        }
+        _ => {
+            if let Err(error) = Input::from_str(input).unwrap().almanac.check_order() {
+                eprintln!("Warning: {error:?}");
+            }
+            println!("Part One: {}", solve_5a(input));
+            println!("Part Two: {}", solve_5b(input));
+        }
+    }
 }
 
-fn solve_5a(input: &str) -> u32 {
+fn solve_5a(input: &str) -> u64 {
     let input = Input::from_str(input).unwrap();
+    let almanac = input.almanac.function("seed", "location").unwrap();
 
     input
         .seeds
         .into_iter()
-        .map(|mut seed| {
-            for map in &input.maps {
-                seed = map.get(seed).0;
-            }
-            seed
-        })
+        .map(|seed| almanac.get(seed))
         .min()
         .unwrap()
 }
 
-fn solve_5b(input: &str) -> u32 {
+fn solve_5b(input: &str) -> u64 {
     let input = Input::from_str(input).unwrap();
-
-    input
+    let seeds: IntervalSet = input
         .seeds
-        .into_iter()
-        .chunks(2)
-        .into_iter()
-        .map(|mut chunk| (chunk.next().unwrap(), chunk.next().unwrap()))
-        .map(|(start_index, length)| {
-            let mut current_seed = start_index;
-            let mut smallest_seed = u32::MAX;
-
-            while current_seed < start_index + length {
-                let mut smallest_margin = u32::MAX;
-                let mut mapped_seed = current_seed;
-                for map in &input.maps {
-                    let (new_seed, margin) = map.get(mapped_seed);
-                    mapped_seed = new_seed;
-                    smallest_margin = smallest_margin.min(margin);
-                }
-                smallest_seed = smallest_seed.min(mapped_seed);
-                // The margin indicates how many subsequent seeds will pass through exactly the
-                // same filters, and will therefore have consecutive (greater) values. We can
-                // skip those, as they won't be the smallest.
-                current_seed += smallest_margin.max(1);
-            }
+        .iter()
+        .copied()
+        .tuples()
+        .map(|(start, length)| start..start + length)
+        .collect();
 
-            smallest_seed
-        })
+    input
+        .almanac
+        .convert_ranges("seed", "location", &seeds)
+        .unwrap()
         .min()
         .unwrap()
 }
 
+fn find_seeds(input: &str, location: u64) -> IntervalSet {
+    let input = Input::from_str(input).unwrap();
+    let almanac = input.almanac.function("seed", "location").unwrap();
+
+    match almanac.inverse() {
+        Some(inverse) => {
+            let seed = inverse.get(location);
+            iter::once(seed..seed + 1).collect()
+        }
+        None => almanac.preimage(&iter::once(location..location + 1).collect()),
+    }
+}
+
 struct Input {
-    seeds: Vec<u32>,
-    maps: Vec<Map>,
+    seeds: Vec<u64>,
+    almanac: Almanac,
 }
 
 impl FromStr for Input {
-    type Err = ();
+    type Err = AlmanacError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let (seeds, maps) = s.trim().split_once("\n\n").unwrap();
@@ -75,87 +96,30 @@ impl FromStr for Input {
             .strip_prefix("seeds: ")
             .unwrap()
             .split(' ')
-            .map(|n| u32::from_str(n).unwrap())
+            .map(|n| u64::from_str(n).unwrap())
             .collect();
         let maps = maps
             .split("\n\n")
-            .map(|line| Map::from_str(line).unwrap())
-            .collect();
-
-        Ok(Input { seeds, maps })
-    }
-}
-
-struct Map {
-    rearrangers: Vec<Rearranger>,
-}
-
-impl Map {
-    fn get(&self, mut index: u32) -> (u32, u32) {
-        let mut margin = u32::MAX;
-        for rearranger in &self.rearrangers {
-            match rearranger.rearrange(index) {
-                None => (),
-                Some((new_index, new_margin)) => {
-                    index = new_index;
-                    margin = margin.min(new_margin);
-                    break;
-                }
-            }
-        }
-        (index, margin)
-    }
-}
+            .map(|map| Map::from_str(map).map_err(AlmanacError::InvalidRange))
+            .collect::<Result<_, _>>()?;
 
-impl FromStr for Map {
-    type Err = ();
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (_, s) = s.split_once(' ').unwrap();
-        let s = s.strip_prefix("map:\n").unwrap();
-        let rearrangers = s
-            .split('\n')
-            .map(|line| Rearranger::from_str(line).unwrap())
-            .collect();
-
-        Ok(Map { rearrangers })
-    }
-}
-
-struct Rearranger {
-    destination_start: u32,
-    source_start: u32,
-    length: u32,
-}
-
-impl Rearranger {
-    fn rearrange(&self, index: u32) -> Option<(u32, u32)> {
-        match index.checked_sub(self.source_start) {
-            Some(relative_index) if relative_index < self.length => Some((
-                self.destination_start + relative_index,
-                self.length - relative_index - 1,
-            )),
-            _ => None,
-        }
-    }
-}
-
-impl FromStr for Rearranger {
-    type Err = ();
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let nums: Vec<_> = s.split(' ').map(|n| u32::from_str(n).unwrap()).collect();
-        Ok(Rearranger {
-            destination_start: nums[0],
-            source_start: nums[1],
-            length: nums[2],
+        Ok(Input {
+            seeds,
+            almanac: Almanac::new(maps)?,
         })
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{solve_5a, solve_5b};
+    use std::{iter, str::FromStr};
+
+    use crate::{
+        almanac::AlmanacError,
+        find_seeds,
+        piecewise::{IntervalSet, Piecewise, PiecewiseError},
+        solve_5a, solve_5b, Input,
+    };
 
     const INPUT: &str = "seeds: 79 14 55 13
 
@@ -201,4 +165,96 @@ humidity-to-location map:
     fn test_5b() {
         assert_eq!(solve_5b(INPUT), 46)
     }
+
+    #[test]
+    fn test_almanac() {
+        let input = Input::from_str(INPUT).unwrap();
+        let almanac = input.almanac.function("seed", "location").unwrap();
+
+        for (seed, location) in [(79, 82), (14, 43), (55, 86), (13, 35)] {
+            assert_eq!(almanac.get(seed), location);
+        }
+        assert_eq!(
+            input.almanac.convert("fertilizer", "humidity", 53).unwrap(),
+            43
+        );
+    }
+
+    #[test]
+    fn test_inverse() {
+        let input = Input::from_str(INPUT).unwrap();
+        let almanac = input.almanac.function("seed", "location").unwrap();
+        let inverse = almanac.inverse().unwrap();
+
+        assert_eq!(inverse.get(46), 82);
+        assert_eq!(almanac.then(&inverse), Piecewise::identity());
+
+        let locations: IntervalSet = iter::once(46..47).collect();
+        assert_eq!(almanac.preimage(&locations).iter().next(), Some(&(82..83)));
+        assert_eq!(find_seeds(INPUT, 46).min(), Some(82));
+    }
+
+    #[test]
+    fn test_category_graph() {
+        let mut sections: Vec<_> = INPUT.trim().split("\n\n").collect();
+        sections[1..].reverse();
+        let reordered = sections.join("\n\n");
+        let input = Input::from_str(&reordered).unwrap();
+
+        assert!(matches!(
+            input.almanac.check_order(),
+            Err(AlmanacError::OutOfOrder { .. })
+        ));
+        assert_eq!(solve_5a(&reordered), 35);
+        assert_eq!(
+            input.almanac.convert("humidity", "fertilizer", 0),
+            Err(AlmanacError::MissingLink {
+                from: "location".to_owned(),
+                to: "fertilizer".to_owned()
+            })
+        );
+
+        let cyclic = INPUT.replace("humidity-to-location", "humidity-to-soil");
+        assert_eq!(
+            Input::from_str(&cyclic).err(),
+            Some(AlmanacError::Cycle(
+                [
+                    "soil",
+                    "fertilizer",
+                    "water",
+                    "light",
+                    "temperature",
+                    "humidity",
+                    "soil"
+                ]
+                .map(String::from)
+                .to_vec()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_invalid_ranges() {
+        // Ranges up to the end of the domain are fine, but not past it.
+        let edge = Piecewise::from_str("0 18446744073709551605 10").unwrap();
+        assert_eq!(edge.get(18446744073709551613), 8);
+        assert_eq!(edge.get(5), 5);
+
+        let out_of_domain = |line: &str| Err(PiecewiseError::OutOfDomain(line.to_owned()));
+        let malformed = |line: &str| Err(PiecewiseError::Malformed(line.to_owned()));
+        for line in ["0 18446744073709551615 2", "18446744073709551610 0 10"] {
+            assert_eq!(Piecewise::from_str(line), out_of_domain(line));
+        }
+        for line in ["1 2", "1 2 x"] {
+            assert_eq!(Piecewise::from_str(line), malformed(line));
+        }
+
+        let overflowing = INPUT.replace("50 98 2", "50 98 18446744073709551615");
+        assert_eq!(
+            Input::from_str(&overflowing).err(),
+            Some(AlmanacError::InvalidRange(PiecewiseError::OutOfDomain(
+                "50 98 18446744073709551615".to_owned()
+            )))
+        );
+    }
 }
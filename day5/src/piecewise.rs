@@ -0,0 +1,273 @@
+use std::{ops::Range, str::FromStr};
+
+/// All values a [`Piecewise`] function is defined on.
+pub const DOMAIN: Range<u64> = 0..u64::MAX;
+
+/// A function on [`DOMAIN`] that shifts each of a series of consecutive half-open intervals by an
+/// offset of its own.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Piecewise {
+    // Sorted, contiguous and covering the whole domain. Neighbouring pieces never share an offset.
+    pieces: Vec<Piece>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Piece {
+    domain: Range<u64>,
+    offset: i128,
+}
+
+impl Piece {
+    fn image(&self) -> Range<u64> {
+        shift(self.domain.start, self.offset)..shift(self.domain.end, self.offset)
+    }
+}
+
+impl Piecewise {
+    pub fn identity() -> Self {
+        Piecewise {
+            pieces: vec![Piece {
+                domain: DOMAIN,
+                offset: 0,
+            }],
+        }
+    }
+
+    pub fn get(&self, value: u64) -> u64 {
+        let index = self
+            .pieces
+            .partition_point(|piece| piece.domain.end <= value);
+        shift(value, self.pieces[index].offset)
+    }
+
+    /// Maps every value in `source` to the corresponding value starting at `destination_start`,
+    /// replacing whatever this function did to those values before.
+    ///
+    /// Panics if the destination range goes past the end of [`DOMAIN`].
+    pub fn overwrite(&mut self, source: Range<u64>, destination_start: u64) {
+        if source.is_empty() {
+            return;
+        }
+        assert!(
+            in_domain(destination_start, source.end - source.start),
+            "range outside the domain"
+        );
+
+        let offset = destination_start as i128 - source.start as i128;
+        let mut pieces: Vec<_> = self
+            .pieces
+            .iter()
+            .flat_map(|piece| {
+                [
+                    piece.domain.start..piece.domain.end.min(source.start),
+                    piece.domain.start.max(source.end)..piece.domain.end,
+                ]
+                .into_iter()
+                .filter(|domain| !domain.is_empty())
+                .map(|domain| Piece {
+                    domain,
+                    offset: piece.offset,
+                })
+            })
+            .collect();
+        pieces.push(Piece {
+            domain: source,
+            offset,
+        });
+
+        self.pieces = normalized(pieces);
+    }
+
+    /// The function that applies `self` first, and `next` to the result.
+    pub fn then(&self, next: &Piecewise) -> Piecewise {
+        let pieces = self
+            .pieces
+            .iter()
+            .flat_map(|piece| {
+                let image = piece.image();
+                next.overlapping(&image).iter().map(move |other| Piece {
+                    domain: shift(image.start.max(other.domain.start), -piece.offset)
+                        ..shift(image.end.min(other.domain.end), -piece.offset),
+                    offset: piece.offset + other.offset,
+                })
+            })
+            .collect();
+
+        Piecewise {
+            pieces: normalized(pieces),
+        }
+    }
+
+    /// All values that any value in `set` is mapped to.
+    pub fn image(&self, set: &IntervalSet) -> IntervalSet {
+        set.iter()
+            .flat_map(|interval| {
+                self.overlapping(interval).iter().map(move |piece| {
+                    let start = interval.start.max(piece.domain.start);
+                    let end = interval.end.min(piece.domain.end);
+                    shift(start, piece.offset)..shift(end, piece.offset)
+                })
+            })
+            .collect()
+    }
+
+    /// All values that are mapped to any value in `set`.
+    pub fn preimage(&self, set: &IntervalSet) -> IntervalSet {
+        self.pieces
+            .iter()
+            .flat_map(|piece| {
+                let image = piece.image();
+                set.overlapping(&image).iter().map(move |interval| {
+                    let start = interval.start.max(image.start);
+                    let end = interval.end.min(image.end);
+                    shift(start, -piece.offset)..shift(end, -piece.offset)
+                })
+            })
+            .collect()
+    }
+
+    /// The function that undoes this one, if every value is mapped to by exactly one value.
+    pub fn inverse(&self) -> Option<Piecewise> {
+        let mut pieces: Vec<_> = self
+            .pieces
+            .iter()
+            .map(|piece| Piece {
+                domain: piece.image(),
+                offset: -piece.offset,
+            })
+            .collect();
+        pieces.sort_by_key(|piece| piece.domain.start);
+
+        let mut covered = DOMAIN.start;
+        for piece in &pieces {
+            if piece.domain.start != covered {
+                return None;
+            }
+            covered = piece.domain.end;
+        }
+        if covered != DOMAIN.end {
+            return None;
+        }
+
+        Some(Piecewise {
+            pieces: normalized(pieces),
+        })
+    }
+
+    fn overlapping(&self, range: &Range<u64>) -> &[Piece] {
+        let start = self
+            .pieces
+            .partition_point(|piece| piece.domain.end <= range.start);
+        let end = self
+            .pieces
+            .partition_point(|piece| piece.domain.start < range.end);
+        &self.pieces[start..end.max(start)]
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum PiecewiseError {
+    /// A line is not three numbers.
+    Malformed(String),
+    /// The source or destination range of a line goes past the end of [`DOMAIN`].
+    OutOfDomain(String),
+}
+
+impl FromStr for Piecewise {
+    type Err = PiecewiseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut function = Piecewise::identity();
+
+        // When ranges overlap, the first one listed wins, so it has to be written last.
+        for line in s.lines().rev() {
+            let nums: Vec<_> = line
+                .split(' ')
+                .map(u64::from_str)
+                .collect::<Result<_, _>>()
+                .map_err(|_| PiecewiseError::Malformed(line.to_owned()))?;
+            let &[destination_start, source_start, length] = nums.as_slice() else {
+                return Err(PiecewiseError::Malformed(line.to_owned()));
+            };
+            if !in_domain(source_start, length) || !in_domain(destination_start, length) {
+                return Err(PiecewiseError::OutOfDomain(line.to_owned()));
+            }
+            function.overwrite(source_start..source_start + length, destination_start);
+        }
+
+        Ok(function)
+    }
+}
+
+/// Whether the range of `length` values from `start` lies within [`DOMAIN`], which ends at the
+/// largest `u64`.
+fn in_domain(start: u64, length: u64) -> bool {
+    start.checked_add(length).is_some()
+}
+
+/// A set of values, stored as sorted, disjoint and non-adjacent half-open intervals.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IntervalSet {
+    intervals: Vec<Range<u64>>,
+}
+
+impl IntervalSet {
+    pub fn iter(&self) -> impl Iterator<Item = &Range<u64>> {
+        self.intervals.iter()
+    }
+
+    pub fn min(&self) -> Option<u64> {
+        self.intervals.first().map(|interval| interval.start)
+    }
+
+    fn overlapping(&self, range: &Range<u64>) -> &[Range<u64>] {
+        let start = self
+            .intervals
+            .partition_point(|interval| interval.end <= range.start);
+        let end = self
+            .intervals
+            .partition_point(|interval| interval.start < range.end);
+        &self.intervals[start..end.max(start)]
+    }
+}
+
+impl FromIterator<Range<u64>> for IntervalSet {
+    fn from_iter<T: IntoIterator<Item = Range<u64>>>(iter: T) -> Self {
+        let mut sorted: Vec<_> = iter
+            .into_iter()
+            .filter(|interval| !interval.is_empty())
+            .collect();
+        sorted.sort_by_key(|interval| interval.start);
+
+        let mut intervals: Vec<Range<u64>> = Vec::with_capacity(sorted.len());
+        for interval in sorted {
+            match intervals.last_mut() {
+                Some(last) if last.end >= interval.start => last.end = last.end.max(interval.end),
+                _ => intervals.push(interval),
+            }
+        }
+
+        IntervalSet { intervals }
+    }
+}
+
+/// Every piece's domain and image lie within [`DOMAIN`], so shifting a value never leaves it.
+fn shift(value: u64, offset: i128) -> u64 {
+    u64::try_from(value as i128 + offset).expect("shifted outside the domain")
+}
+
+fn normalized(mut pieces: Vec<Piece>) -> Vec<Piece> {
+    pieces.sort_by_key(|piece| piece.domain.start);
+
+    let mut merged: Vec<Piece> = Vec::with_capacity(pieces.len());
+    for piece in pieces {
+        match merged.last_mut() {
+            Some(last) if last.offset == piece.offset && last.domain.end == piece.domain.start => {
+                last.domain.end = piece.domain.end
+            }
+            _ => merged.push(piece),
+        }
+    }
+
+    merged
+}
@@ -30,33 +30,31 @@ fn solve(time_limit: u64, record_distance: u64) -> u64 {
 }
 
 fn solve_efficiently(time_limit: u64, record_distance: u64) -> u64 {
-    let temp1 = (time_limit as f64
-        + (time_limit as f64 * time_limit as f64 - 4.0 * record_distance as f64).sqrt())
-        / 2.0;
-    let temp2 = (time_limit as f64
-        - (time_limit as f64 * time_limit as f64 - 4.0 * record_distance as f64).sqrt())
-        / 2.0;
-    let right = temp1.max(temp2);
-    let left = temp1.min(temp2);
-    assert!(left < right);
-    let left_clamped = left.clamp(0.0, time_limit as f64);
-    let right_clamped = right.clamp(0.0, time_limit as f64);
-
-    let min = if left_clamped.fract() == 0.0 {
-        left_clamped + 1.0
-    } else {
-        left_clamped.ceil()
+    let time_limit = time_limit as u128;
+    let record_distance = record_distance as u128;
+    let wins = |time: u128| time * (time_limit - time) > record_distance;
+
+    // Winning times lie strictly between the roots of `time * (time_limit - time) = record`,
+    // which are `(time_limit ± sqrt(time_limit² - 4 * record)) / 2`.
+    let discriminant = match (time_limit * time_limit).checked_sub(4 * record_distance) {
+        Some(discriminant) => discriminant,
+        None => return 0,
     };
-    let max = if right_clamped.fract() == 0.0 {
-        right_clamped - 1.0
-    } else {
-        right_clamped.floor()
-    };
-    if max >= min {
-        (max - min + 1.0) as u64
-    } else {
-        0
+    let mut first = (time_limit - discriminant.isqrt()) / 2;
+
+    // The integer square root is rounded down, so the estimate may be off by one either way.
+    while first > 0 && wins(first - 1) {
+        first -= 1;
+    }
+    while first <= time_limit / 2 && !wins(first) {
+        first += 1;
     }
+    if first > time_limit / 2 {
+        return 0;
+    }
+
+    // The distance is symmetric around `time_limit / 2`.
+    (time_limit - 2 * first + 1) as u64
 }
 
 fn parse(s: &str) -> impl Iterator<Item = (u32, u32)> + '_ {
@@ -103,14 +101,39 @@ fn parse_as_one(s: &str) -> (u64, u64) {
 
 #[cfg(test)]
 mod tests {
-    use crate::solve_6a;
+    use proptest::prelude::*;
 
-    #[test]
-    fn test_6a() {
-        const INPUT: &str = "Time:      7  15   30
+    use crate::{solve, solve_6a, solve_6b, solve_efficiently};
+
+    const INPUT: &str = "Time:      7  15   30
 Distance:  9  40  200
 ";
 
+    #[test]
+    fn test_6a() {
         assert_eq!(solve_6a(INPUT), 288)
     }
+
+    #[test]
+    fn test_6b() {
+        assert_eq!(solve_6b(INPUT), 71503)
+    }
+
+    #[test]
+    fn test_degenerate() {
+        assert_eq!(solve_efficiently(0, 0), 0);
+        assert_eq!(solve_efficiently(10, 25), 0);
+        assert_eq!(solve_efficiently(10, 24), 1);
+        assert_eq!(solve_efficiently(u64::MAX, 0), u64::MAX - 1);
+    }
+
+    proptest! {
+        #[test]
+        fn test_efficient_matches_brute_force(time_limit in 0..1000_u64, record_distance in 0..300_000_u64) {
+            prop_assert_eq!(
+                solve_efficiently(time_limit, record_distance),
+                solve(time_limit, record_distance)
+            );
+        }
+    }
 }
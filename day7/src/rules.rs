@@ -0,0 +1,133 @@
+use std::str::FromStr;
+
+use itertools::Itertools;
+
+/// Everything that decides how strong a hand of Camel Cards is.
+pub struct RuleSet {
+    /// Card labels from weakest to strongest.
+    order: Vec<char>,
+    /// Labels that act like whichever card makes the hand strongest, while still being compared by
+    /// their own position in `order` when breaking ties.
+    wildcards: Vec<char>,
+    classification: Classification,
+}
+
+pub enum Classification {
+    /// Hands are compared by the sizes of their groups of equal cards, largest first, which works
+    /// for any hand size.
+    Groups,
+    /// Only the listed group sizes, from weakest to strongest, are valid hand types.
+    Ranked(Vec<Vec<usize>>),
+}
+
+impl RuleSet {
+    pub fn new(order: &str, wildcards: &str, classification: Classification) -> Self {
+        RuleSet {
+            order: order.chars().collect(),
+            wildcards: wildcards.chars().collect(),
+            classification,
+        }
+    }
+
+    pub fn standard() -> Self {
+        RuleSet::new("23456789TJQKA", "", Classification::Groups)
+    }
+
+    pub fn jokers() -> Self {
+        RuleSet::new("J23456789TQKA", "J", Classification::Groups)
+    }
+
+    pub fn deuces_wild() -> Self {
+        RuleSet::new("23456789TJQKA", "2", Classification::Groups)
+    }
+
+    /// Two pair beats three of a kind, since it's harder to get with five cards.
+    pub fn two_pair_over_trips() -> Self {
+        RuleSet::new(
+            "23456789TJQKA",
+            "",
+            Classification::Ranked(vec![
+                vec![1, 1, 1, 1, 1],
+                vec![2, 1, 1, 1],
+                vec![3, 1, 1],
+                vec![2, 2, 1],
+                vec![3, 2],
+                vec![4, 1],
+                vec![5],
+            ]),
+        )
+    }
+
+    /// A key that sorts hands from weakest to strongest, or `None` if the hand contains unknown
+    /// cards or has a type that is not ranked.
+    pub fn strength(&self, hand: &str) -> Option<(Vec<usize>, Vec<usize>)> {
+        let cards = hand
+            .chars()
+            .map(|card| self.order.iter().position(|&c| c == card))
+            .collect::<Option<Vec<_>>>()?;
+
+        Some((self.hand_type(hand)?, cards))
+    }
+
+    fn hand_type(&self, hand: &str) -> Option<Vec<usize>> {
+        let wildcards = hand.chars().filter(|c| self.wildcards.contains(c)).count();
+        let mut groups: Vec<_> = hand
+            .chars()
+            .filter(|c| !self.wildcards.contains(c))
+            .counts()
+            .into_values()
+            .collect();
+        groups.sort_by(|a, b| b.cmp(a));
+
+        match &self.classification {
+            Classification::Groups => {
+                // Adding all wildcards to the largest group is always the best choice here.
+                match groups.first_mut() {
+                    Some(largest) => *largest += wildcards,
+                    None if wildcards > 0 => groups.push(wildcards),
+                    None => (),
+                }
+                Some(groups)
+            }
+            Classification::Ranked(types) => shapes(groups, wildcards)
+                .into_iter()
+                .filter_map(|shape| types.iter().position(|t| *t == shape))
+                .max()
+                .map(|rank| vec![rank]),
+        }
+    }
+}
+
+/// Expects the card order, optionally followed by a slash and the wildcards, such as
+/// `J23456789TQKA/J`.
+impl FromStr for RuleSet {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (order, wildcards) = s.split_once('/').unwrap_or((s, ""));
+        Ok(RuleSet::new(order, wildcards, Classification::Groups))
+    }
+}
+
+/// All group sizes that can be made by adding the wildcards to the given groups.
+fn shapes(groups: Vec<usize>, wildcards: usize) -> Vec<Vec<usize>> {
+    if wildcards == 0 {
+        return vec![groups];
+    }
+
+    let mut options: Vec<_> = (0..groups.len())
+        .map(|index| {
+            let mut option = groups.clone();
+            option[index] += 1;
+            option
+        })
+        .chain([groups.iter().copied().chain([1]).collect()])
+        .flat_map(|mut option| {
+            option.sort_by(|a, b| b.cmp(a));
+            shapes(option, wildcards - 1)
+        })
+        .collect();
+    options.sort();
+    options.dedup();
+    options
+}
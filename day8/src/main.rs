@@ -1,113 +1,276 @@
-use std::collections::BTreeMap;
+use std::{collections::HashMap, env, fmt::Write, iter};
 
 fn main() {
     let input = include_str!("../../inputs/input8");
 
-    println!("Part One: {}", solve_8a(input));
-    println!("Part Two: {}", solve_8b(input));
+    match env::args().nth(1).as_deref() {
+        Some("dot") => {
+            let network = Network::from_str(input);
+            let cycles = network.cycles(|name| name.ends_with('A'), |name| name.ends_with('Z'));
+            print!("{}", network.to_dot(&cycles));
+        }
+        _ => {
+            println!("Part One: {}", solve_8a(input));
+            println!("Part Two: {}", solve_8b(input));
+        }
+    }
 }
 
-fn solve_8a(input: &str) -> u32 {
-    let input = Input::from_str(input);
-
-    let mut current = &input.locations[Input::START];
-    let mut step_index = 0;
-    let mut steps = 1;
+fn solve_8a(input: &str) -> u128 {
+    solve(input, |name| name == "AAA", |name| name == "ZZZ").unwrap()
+}
 
-    loop {
-        let direction = input.directions[step_index];
-        let new_location = current.locations[&direction];
+fn solve_8b(input: &str) -> u128 {
+    solve(
+        input,
+        |name| name.ends_with('A'),
+        |name| name.ends_with('Z'),
+    )
+    .unwrap()
+}
 
-        if new_location == Input::END {
-            return steps;
-        }
+/// The number of steps until the ghosts starting at every start node are all at an end node.
+fn solve(
+    input: &str,
+    is_start: impl Fn(&str) -> bool,
+    is_end: impl Fn(&str) -> bool,
+) -> Option<u128> {
+    let network = Network::from_str(input);
+    Cycle::first_common_hit(&network.cycles(is_start, is_end))
+}
 
-        current = &input.locations[new_location];
-        step_index = (step_index + 1) % input.directions.len();
-        steps += 1;
-    }
+/// The path of a single ghost, which eventually has to repeat as there are only finitely many
+/// combinations of location and instruction.
+struct Cycle {
+    /// The number of steps before the ghost enters the cycle.
+    start: usize,
+    /// The number of steps it takes to go around the cycle once.
+    period: usize,
+    /// Steps at which the ghost is at an end, only counting the first time around the cycle.
+    hits: Vec<usize>,
+    /// The nodes visited at each step, up to and including the first time around the cycle.
+    path: Vec<u32>,
 }
 
-fn solve_8b(input: &str) -> u128 {
-    let input = Input::from_str(input);
-
-    let mut current: Vec<_> = input
-        .locations
-        .iter()
-        .filter_map(|(name, location)| name.ends_with('A').then_some(location))
-        .collect();
-    let mut step_index = 0;
-    let mut steps = current.iter().map(|_| 0).collect::<Vec<_>>();
-    let mut done = current.iter().map(|_| false).collect::<Vec<_>>();
-
-    while !done.iter().all(|done| *done) {
-        let direction = input.directions[step_index];
-        let new_locations: Vec<_> = current
+impl Cycle {
+    fn find(network: &Network, start: u32, ends: &[bool]) -> Self {
+        // The ghost only repeats once both its node and its place in the directions do.
+        let step = |&(node, step_index): &(u32, usize)| {
+            let next = network.step(node, step_index);
+            (next, (step_index + 1) % network.directions.len())
+        };
+        let cycle::Cycle { prefix, period } = cycle::brent(&(start, 0), step);
+
+        let path: Vec<_> = iter::successors(Some((start, 0)), |state| Some(step(state)))
+            .take(prefix + period)
+            .map(|(node, _)| node)
+            .collect();
+        let hits = path
             .iter()
-            .map(|location| location.locations[&direction])
+            .enumerate()
+            .filter(|(_, &node)| ends[node as usize])
+            .map(|(steps, _)| steps)
             .collect();
 
-        for (index, name) in new_locations.iter().enumerate() {
-            if !done[index] {
-                steps[index] += 1;
-            }
-            if name.ends_with('Z') {
-                done[index] = true;
-            }
+        Cycle {
+            start: prefix,
+            period,
+            hits,
+            path,
+        }
+    }
+
+    fn is_hit(&self, steps: u128) -> bool {
+        if steps < self.start as u128 {
+            self.hits.contains(&(steps as usize))
+        } else {
+            let offset = (steps - self.start as u128) % self.period as u128;
+            self.hits.contains(&(self.start + offset as usize))
         }
+    }
 
-        for (index, location) in current.iter_mut().enumerate() {
-            *location = &input.locations[new_locations[index]];
+    /// The first step, after starting, at which all ghosts are at an end at the same time.
+    fn first_common_hit(cycles: &[Cycle]) -> Option<u128> {
+        // Hits before some ghost entered its cycle can only happen once, so check them directly.
+        let early = cycles
+            .iter()
+            .flat_map(|cycle| cycle.hits.iter().filter(|&&hit| hit < cycle.start))
+            .map(|&hit| hit as u128)
+            .filter(|&hit| hit > 0 && cycles.iter().all(|cycle| cycle.is_hit(hit)))
+            .min();
+        if early.is_some() {
+            return early;
         }
-        step_index = (step_index + 1) % input.directions.len();
+
+        // After that, every ghost is in its cycle, so each hit repeats with the period of its
+        // cycle. Any combination of hits, one for each ghost, can line up.
+        let latest_start = cycles.iter().map(|cycle| cycle.start).max()? as u128;
+        let mut combinations = vec![(0_u128, 1_u128)];
+        for cycle in cycles {
+            combinations = combinations
+                .into_iter()
+                .flat_map(|(remainder, modulus)| {
+                    cycle
+                        .hits
+                        .iter()
+                        .filter(|&&hit| hit >= cycle.start)
+                        .filter_map(move |&hit| {
+                            crt(remainder, modulus, hit as u128, cycle.period as u128)
+                        })
+                })
+                .collect();
+        }
+
+        combinations
+            .into_iter()
+            .map(|(remainder, modulus)| {
+                let earliest = latest_start.max(1);
+                if remainder >= earliest {
+                    remainder
+                } else {
+                    remainder + (earliest - remainder).div_ceil(modulus) * modulus
+                }
+            })
+            .min()
+    }
+}
+
+/// Combines `x = a (mod n)` and `x = b (mod m)` into a single congruence, if there is a solution.
+fn crt(a: u128, n: u128, b: u128, m: u128) -> Option<(u128, u128)> {
+    let (gcd, p, _) = extended_gcd(n as i128, m as i128);
+    let gcd = gcd as u128;
+    let (a, b) = (a % n, b % m);
+    if a.abs_diff(b) % gcd != 0 {
+        return None;
     }
 
-    // Wrong answer, just put all numbers in a lcm calculator
-    dbg!(steps).iter().product()
+    let lcm = n / gcd * m;
+    // Solve n * k = b - a (mod m), where p is the inverse of n / gcd modulo m / gcd.
+    let m_reduced = (m / gcd) as i128;
+    let k =
+        ((b as i128 - a as i128) / gcd as i128 % m_reduced * p % m_reduced + m_reduced) % m_reduced;
+    Some(((a + n * k as u128) % lcm, lcm))
 }
 
-struct Input<'a> {
-    directions: Vec<Direction>,
-    locations: BTreeMap<&'a str, Location<'a>>,
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        (a, 1, 0)
+    } else {
+        let (gcd, x, y) = extended_gcd(b, a % b);
+        (gcd, y, x - a / b * y)
+    }
 }
 
-impl<'a> Input<'a> {
-    const START: &'static str = "AAA";
-    const END: &'static str = "ZZZ";
+/// The network with every node name replaced by its index in `names`.
+struct Network<'a> {
+    directions: Vec<Direction>,
+    names: Vec<&'a str>,
+    /// The left and right neighbour of every node.
+    edges: Vec<[u32; 2]>,
+}
 
+impl<'a> Network<'a> {
     fn from_str(input: &'a str) -> Self {
-        let (directions, locations) = input.split_once("\n\n").unwrap();
+        let (directions, nodes) = input.split_once("\n\n").unwrap();
 
         let directions = directions.chars().map(Direction::from_char).collect();
-        let locations = locations.lines().map(Location::from_str).collect();
+        let nodes: Vec<_> = nodes.lines().map(parse_node).collect();
 
-        Input {
+        let names: Vec<_> = nodes.iter().map(|(name, _)| *name).collect();
+        let ids: HashMap<_, _> = names
+            .iter()
+            .enumerate()
+            .map(|(id, name)| (*name, id as u32))
+            .collect();
+        let edges = nodes
+            .iter()
+            .map(|(_, neighbours)| neighbours.map(|name| ids[name]))
+            .collect();
+
+        Network {
             directions,
-            locations,
+            names,
+            edges,
         }
     }
-}
 
-struct Location<'a> {
-    locations: BTreeMap<Direction, &'a str>,
-}
+    fn step(&self, node: u32, step_index: usize) -> u32 {
+        self.edges[node as usize][self.directions[step_index] as usize]
+    }
+
+    fn cycles(&self, is_start: impl Fn(&str) -> bool, is_end: impl Fn(&str) -> bool) -> Vec<Cycle> {
+        let ends: Vec<_> = self.names.iter().map(|name| is_end(name)).collect();
+
+        (0..self.names.len() as u32)
+            .filter(|&node| is_start(self.names[node as usize]))
+            .map(|node| Cycle::find(self, node, &ends))
+            .collect()
+    }
 
-impl<'a> Location<'a> {
-    fn from_str(input: &'a str) -> (&'a str, Self) {
-        let (name, location) = input.split_once(" = ").unwrap();
+    /// Renders the network in Graphviz DOT format, with the cycle of every ghost in its own colour.
+    fn to_dot(&self, cycles: &[Cycle]) -> String {
+        const COLOURS: [&str; 6] = ["red", "blue", "darkgreen", "orange", "purple", "brown"];
 
-        let location = location.strip_prefix('(').unwrap();
-        let location = location.strip_suffix(')').unwrap();
-        let (left, right) = location.split_once(", ").unwrap();
+        let mut node_colours = HashMap::new();
+        let mut edge_colours = HashMap::new();
+        for (index, cycle) in cycles.iter().enumerate() {
+            let colour = COLOURS[index % COLOURS.len()];
+            let nodes = &cycle.path[cycle.start..];
+            for (step, &node) in nodes.iter().enumerate() {
+                let next = nodes.get(step + 1).copied().unwrap_or(nodes[0]);
+                node_colours.insert(node, colour);
+                edge_colours.insert((node, next), colour);
+            }
+        }
+        let starts: Vec<_> = cycles.iter().map(|cycle| cycle.path[0]).collect();
+        let ends: Vec<_> = cycles
+            .iter()
+            .flat_map(|cycle| cycle.hits.iter().map(|&hit| cycle.path[hit]))
+            .collect();
 
-        let mut locations = BTreeMap::new();
-        locations.insert(Direction::Left, left);
-        locations.insert(Direction::Right, right);
+        let mut dot = String::from("digraph network {\n");
+        for (node, name) in self.names.iter().enumerate() {
+            let node = node as u32;
+            let shape = if starts.contains(&node) {
+                "box"
+            } else if ends.contains(&node) {
+                "doublecircle"
+            } else {
+                "ellipse"
+            };
+            let colour = node_colours.get(&node).unwrap_or(&"black");
+            writeln!(dot, "    \"{name}\" [shape={shape}, color={colour}];").unwrap();
+        }
+        for (node, neighbours) in self.edges.iter().enumerate() {
+            let node = node as u32;
+            for (label, &neighbour) in ["L", "R"].into_iter().zip(neighbours) {
+                let (from, to) = (self.names[node as usize], self.names[neighbour as usize]);
+                match edge_colours.get(&(node, neighbour)) {
+                    Some(colour) => writeln!(
+                        dot,
+                        "    \"{from}\" -> \"{to}\" [label={label}, color={colour}, penwidth=2];"
+                    ),
+                    None => writeln!(dot, "    \"{from}\" -> \"{to}\" [label={label}];"),
+                }
+                .unwrap();
+            }
+        }
+        dot.push_str("}\n");
 
-        (name, Self { locations })
+        dot
     }
 }
 
+fn parse_node(input: &str) -> (&str, [&str; 2]) {
+    let (name, neighbours) = input.split_once(" = ").unwrap();
+
+    let neighbours = neighbours.strip_prefix('(').unwrap();
+    let neighbours = neighbours.strip_suffix(')').unwrap();
+    let (left, right) = neighbours.split_once(", ").unwrap();
+
+    (name, [left, right])
+}
+
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
 enum Direction {
     Left,
@@ -123,3 +286,80 @@ impl Direction {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{solve, solve_8a, solve_8b, Network};
+
+    #[test]
+    fn test_8a() {
+        const INPUT: &str = "LLR
+
+AAA = (BBB, BBB)
+BBB = (AAA, ZZZ)
+ZZZ = (ZZZ, ZZZ)
+";
+
+        assert_eq!(solve_8a(INPUT), 6)
+    }
+
+    #[test]
+    fn test_8b() {
+        const INPUT: &str = "LR
+
+11A = (11B, XXX)
+11B = (XXX, 11Z)
+11Z = (11B, XXX)
+22A = (22B, XXX)
+22B = (22C, 22C)
+22C = (22Z, 22Z)
+22Z = (22B, 22B)
+XXX = (XXX, XXX)
+";
+
+        assert_eq!(solve_8b(INPUT), 6)
+    }
+
+    #[test]
+    fn test_offset_cycles() {
+        // The first ghost ends up at a Z every three steps starting at 1, the second every two
+        // steps starting at 2, so multiplying the first hits would give 2 instead of 4.
+        const INPUT: &str = "L
+
+11A = (11Z, 11Z)
+11Z = (11B, 11B)
+11B = (11C, 11C)
+11C = (11Z, 11Z)
+22A = (22B, 22B)
+22B = (22Z, 22Z)
+22Z = (22B, 22B)
+";
+
+        assert_eq!(solve_8b(INPUT), 4)
+    }
+
+    #[test]
+    fn test_predicates() {
+        const INPUT: &str = "L
+
+11A = (11Z, 11Z)
+11Z = (11B, 11B)
+11B = (11C, 11C)
+11C = (11Z, 11Z)
+";
+
+        assert_eq!(
+            solve(INPUT, |name| name == "11B", |name| name == "11Z"),
+            Some(2)
+        );
+        assert_eq!(
+            solve(INPUT, |name| name == "11B", |name| name == "11A"),
+            None
+        );
+
+        let network = Network::from_str(INPUT);
+        let dot = network.to_dot(&network.cycles(|name| name == "11A", |name| name == "11Z"));
+        assert!(dot.contains("\"11A\" [shape=box, color=black];"));
+        assert!(dot.contains("\"11C\" -> \"11Z\" [label=L, color=red, penwidth=2];"));
+    }
+}
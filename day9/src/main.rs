@@ -1,114 +1,230 @@
-use std::str::FromStr;
+use std::{env, str::FromStr};
 
 fn main() {
     let input = include_str!("../../inputs/input9");
 
-    println!("Part One: {}", solve_9a(input));
-    println!("Part Two: {}", solve_9b(input));
+    match env::args().nth(1).as_deref() {
+        Some("at") => {
+            let index = env::args().nth(2).unwrap().parse().unwrap();
+            for polynomial in parse(input) {
+                match polynomial.evaluate(index) {
+                    Some(value) => println!("degree {}: {value}", polynomial.degree()),
+                    None => println!("degree {}: overflow", polynomial.degree()),
+                }
+            }
+        }
+        _ => {
+            let unconfirmed = unconfirmed(input);
+            if !unconfirmed.is_empty() {
+                eprintln!(
+                    "warning: lines {unconfirmed:?} never reach a row of zeroes, so they are \
+                     extrapolated from every difference"
+                );
+            }
+            println!("Part One: {}", solve_9a(input));
+            println!("Part Two: {}", solve_9b(input));
+        }
+    }
 }
 
-fn solve_9a(input: &str) -> i32 {
-    input
-        .lines()
-        .map(|line| line.parse().unwrap())
-        .map(|seq: Sequence| seq.extrapolate())
+fn solve_9a(input: &str) -> i128 {
+    parse(input)
+        .map(|polynomial| polynomial.evaluate(polynomial.next_index()).unwrap())
         .sum()
 }
 
-fn solve_9b(input: &str) -> i32 {
+fn solve_9b(input: &str) -> i128 {
+    parse(input)
+        .map(|polynomial| polynomial.evaluate(-1).unwrap())
+        .sum()
+}
+
+fn parse(input: &str) -> impl Iterator<Item = Polynomial> + '_ {
+    input.lines().map(|line| {
+        let sequence: Sequence = line.parse().unwrap();
+        Polynomial::interpolate(&sequence.numbers).unwrap()
+    })
+}
+
+/// The zero-based indices of the lines whose differences never become all zero.
+fn unconfirmed(input: &str) -> Vec<usize> {
     input
         .lines()
-        .map(|line| line.parse().unwrap())
-        .map(|seq: Sequence| seq.extrapolate_before())
-        .sum()
+        .enumerate()
+        .filter(|(_, line)| {
+            let sequence: Sequence = line.parse().unwrap();
+            Polynomial::fit(&sequence.numbers).err() == Some(FitError::NoZeroRow)
+        })
+        .map(|(index, _)| index)
+        .collect()
 }
 
-#[derive(Clone)]
 pub struct Sequence {
-    numbers: Vec<i32>,
+    numbers: Vec<i128>,
 }
 
-impl Sequence {
-    pub fn is_zero(&self) -> bool {
-        self.numbers.iter().all(|n| *n == 0)
+impl FromStr for Sequence {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Sequence {
+            numbers: s.split(' ').map(|number| number.parse().unwrap()).collect(),
+        })
     }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum FitError {
+    Empty,
+    /// The differences never became all zero, so the sequence has too few numbers to tell which
+    /// polynomial produced it.
+    NoZeroRow,
+}
+
+/// A polynomial in Newton form, `p(x) = sum of differences[k] * (x choose k)`, where
+/// `differences[k]` is the first number in the k-th row of the difference table.
+#[derive(Debug)]
+pub struct Polynomial {
+    differences: Vec<i128>,
+    /// The number of values the polynomial was fitted to.
+    len: usize,
+}
 
-    fn derivative(&self) -> Sequence {
-        let mut offset = self.numbers.clone();
-        let _ = offset.remove(0);
-        for (index, n) in offset.iter_mut().enumerate() {
-            *n -= self.numbers[index];
+impl Polynomial {
+    /// Finds the polynomial of lowest degree that produces `numbers` at indices 0, 1, 2, ...
+    pub fn fit(numbers: &[i128]) -> Result<Self, FitError> {
+        if numbers.is_empty() {
+            return Err(FitError::Empty);
         }
-        Sequence { numbers: offset }
-    }
 
-    pub fn extrapolate(&self) -> i32 {
-        let mut derivatives = Vec::new();
-        let mut current = self.clone();
-        derivatives.push(current.clone());
-        loop {
-            current = current.derivative();
-            derivatives.push(current.clone());
-            if current.is_zero() {
-                break;
+        let mut differences = Vec::new();
+        let mut row = numbers.to_vec();
+        while !row.iter().all(|n| *n == 0) {
+            if row.len() == 1 {
+                return Err(FitError::NoZeroRow);
             }
+            differences.push(row[0]);
+            row = row.windows(2).map(|pair| pair[1] - pair[0]).collect();
         }
 
-        let mut extrapolated_value = 0;
-        loop {
-            derivatives.pop();
-            match derivatives.last() {
-                None => return extrapolated_value,
-                Some(last) => extrapolated_value += last.numbers.last().unwrap(),
+        Ok(Polynomial {
+            differences,
+            len: numbers.len(),
+        })
+    }
+
+    /// Like `fit`, but if the differences never become all zero, it keeps going until the rows run
+    /// out, as if the empty row were all zeroes. That is the polynomial of degree `len - 1`
+    /// through all the numbers.
+    pub fn interpolate(numbers: &[i128]) -> Result<Self, FitError> {
+        match Polynomial::fit(numbers) {
+            Err(FitError::NoZeroRow) => {
+                let mut differences = Vec::new();
+                let mut row = numbers.to_vec();
+                while let Some(&first) = row.first() {
+                    differences.push(first);
+                    row = row.windows(2).map(|pair| pair[1] - pair[0]).collect();
+                }
+
+                Ok(Polynomial {
+                    differences,
+                    len: numbers.len(),
+                })
             }
+            result => result,
         }
     }
 
-    pub fn extrapolate_before(&self) -> i32 {
-        let mut derivatives = Vec::new();
-        let mut current = self.clone();
-        derivatives.push(current.clone());
-        loop {
-            current = current.derivative();
-            derivatives.push(current.clone());
-            if current.is_zero() {
+    /// The first index after the values the polynomial was fitted to.
+    pub fn next_index(&self) -> i128 {
+        self.len as i128
+    }
+
+    /// The degree of the polynomial, where the zero polynomial has degree 0 as well.
+    pub fn degree(&self) -> usize {
+        self.differences.len().saturating_sub(1)
+    }
+
+    /// The value at `index`, or `None` if it does not fit in an `i128`.
+    pub fn evaluate(&self, index: i128) -> Option<i128> {
+        let mut value = 0_i128;
+        let mut binomial = 1_i128;
+
+        for (k, difference) in self.differences.iter().enumerate() {
+            value = value.checked_add(difference.checked_mul(binomial)?)?;
+            if k + 1 == self.differences.len() {
                 break;
             }
-        }
 
-        let mut extrapolated_value = 0;
-        loop {
-            derivatives.pop();
-            match derivatives.last() {
-                None => return extrapolated_value,
-                Some(last) => {
-                    extrapolated_value = last.numbers.first().unwrap() - extrapolated_value
-                }
-            }
+            // (index choose k + 1) is a whole number, so once the common factor of the numerator
+            // and denominator is gone, the denominator divides `binomial`.
+            let (numerator, denominator) = (index - k as i128, k as i128 + 1);
+            let common = gcd(numerator, denominator);
+            binomial = (binomial / (denominator / common)).checked_mul(numerator / common)?;
         }
+
+        Some(value)
     }
 }
 
-impl FromStr for Sequence {
-    type Err = ();
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Sequence {
-            numbers: s.split(' ').map(|number| number.parse().unwrap()).collect(),
-        })
+fn gcd(a: i128, b: i128) -> i128 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::solve_9a;
+    use crate::{solve_9a, solve_9b, unconfirmed, FitError, Polynomial};
 
-    #[test]
-    fn test_9a() {
-        const INPUT: &str = "0 3 6 9 12 15
+    const INPUT: &str = "0 3 6 9 12 15
 1 3 6 10 15 21
 10 13 16 21 30 45";
 
+    #[test]
+    fn test_9a() {
         assert_eq!(solve_9a(INPUT), 114)
     }
+
+    #[test]
+    fn test_9b() {
+        assert_eq!(solve_9b(INPUT), 2)
+    }
+
+    #[test]
+    fn test_polynomial() {
+        let squares: Vec<_> = (0..5).map(|x: i128| x * x - 3).collect();
+        let polynomial = Polynomial::fit(&squares).unwrap();
+
+        assert_eq!(polynomial.degree(), 2);
+        assert_eq!(polynomial.evaluate(-7), Some(46));
+        assert_eq!(polynomial.evaluate(1_000_000), Some(999_999_999_997));
+        // Values that fit, even though the next binomial coefficient would not.
+        let squares = Polynomial::fit(&[0, 1, 4, 9]).unwrap();
+        assert_eq!(
+            squares.evaluate(20_000_000_000_000),
+            Some(400_000_000_000_000_000_000_000_000)
+        );
+        assert_eq!(squares.evaluate(i128::MAX / 2), None);
+        assert_eq!(Polynomial::fit(&[0, 0]).unwrap().degree(), 0);
+
+        assert_eq!(Polynomial::fit(&[]).err(), Some(FitError::Empty));
+        assert_eq!(Polynomial::fit(&[1, 2, 4]).err(), Some(FitError::NoZeroRow));
+    }
+
+    #[test]
+    fn test_no_zero_row() {
+        // 1 2 4, then 1 2, then 1, then nothing, which counts as zero.
+        let polynomial = Polynomial::interpolate(&[1, 2, 4]).unwrap();
+        assert_eq!(polynomial.degree(), 2);
+        assert_eq!(polynomial.evaluate(3), Some(7));
+        assert_eq!(polynomial.evaluate(-1), Some(1));
+
+        let input = "1 2 4\n0 3 6 9";
+        assert_eq!(solve_9a(input), 7 + 12);
+        assert_eq!(solve_9b(input), 1 - 3);
+        assert_eq!(unconfirmed(input), vec![0]);
+    }
 }